@@ -232,7 +232,6 @@ impl framework::Example for Example {
         .build();
 
         let pipeline_wire = if context
-            .device()
             .features()
             .contains(wgt::Features::POLYGON_MODE_LINE)
         {
@@ -316,12 +315,17 @@ impl framework::Example for Example {
                 pipeline: self.pipeline.clone(),
                 vertices: vec![self.vertex_buf.slice(..)],
                 indices: Some(self.index_buf.slice(..)),
+                index_format: wgpu::IndexFormat::Uint16,
+                indirect: None,
                 element_range: 0..self.index_count,
                 instance_range: 0..1,
-                rasteriser_state: RasteriserState {
+                base_vertex: 0,
+                instance_base: 0,
+                push_constant_data: vec![],
+                rasteriser_state: Some(RasteriserState {
                     cull_mode: Some(wgpu::Face::Back),
                     ..Default::default()
-                },
+                }),
             });
 
             if let Some(ref pipe) = self.pipeline_wire {
@@ -331,13 +335,18 @@ impl framework::Example for Example {
                     pipeline: pipe.clone(),
                     vertices: vec![self.vertex_buf.slice(..)],
                     indices: Some(self.index_buf.slice(..)),
+                    index_format: wgpu::IndexFormat::Uint16,
+                    indirect: None,
                     element_range: 0..self.index_count,
                     instance_range: 0..1,
-                    rasteriser_state: RasteriserState {
+                    base_vertex: 0,
+                    instance_base: 0,
+                    push_constant_data: vec![],
+                    rasteriser_state: Some(RasteriserState {
                         cull_mode: Some(wgpu::Face::Back),
                         polygon_mode: wgpu::PolygonMode::Line,
                         ..Default::default()
-                    },
+                    }),
                 });
             }
         }
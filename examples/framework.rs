@@ -218,7 +218,7 @@ async fn setup<E: Example>(title: &str) -> Setup {
         .await
         .expect("Unable to find a suitable GPU adapter!");
 
-    let context = Context::new(device, queue);
+    let context = Context::with_adapter_info(device, queue, adapter.get_info());
 
     Setup {
         window,
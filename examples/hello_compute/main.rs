@@ -1,5 +1,6 @@
 use simple_wgpu::{
-    BindGroupBuilder, Buffer, CommandEncoder, ComputePipelineBuilder, Context, Dispatch, Shader,
+    BindGroupBuilder, Buffer, CommandEncoder, ComputePipelineBuilder, Context, ContextOptions,
+    Dispatch, Shader,
 };
 use std::str::FromStr;
 use wgpu::include_wgsl;
@@ -38,41 +39,21 @@ async fn execute_gpu(numbers: &[u32]) -> Option<Vec<u32>> {
     // Instantiates instance of WebGPU
     let instance = wgpu::Instance::default();
 
-    // `request_adapter` instantiates the general connection to the GPU
-    let adapter = instance
-        .request_adapter(&wgpu::RequestAdapterOptions::default())
-        .await?;
-
-    // `request_device` instantiates the feature specific connection to the GPU, defining some parameters,
-    //  `features` being the available features.
-    let (device, queue) = adapter
-        .request_device(
-            &wgpu::DeviceDescriptor {
-                label: None,
-                features: wgpu::Features::empty(),
-                limits: wgpu::Limits::downlevel_defaults(),
-            },
-            None,
-        )
-        .await
-        .unwrap();
+    // Instantiates the connection to the GPU, with the default features and limits
+    let context = Context::from_instance(&instance, ContextOptions::default()).await?;
 
-    let info = adapter.get_info();
     // skip this on LavaPipe temporarily
-    if info.vendor == 0x10005 {
+    if context
+        .adapter_info()
+        .is_some_and(|info| info.vendor == 0x10005)
+    {
         return None;
     }
 
-    execute_gpu_inner(device, queue, numbers).await
+    execute_gpu_inner(context, numbers).await
 }
 
-async fn execute_gpu_inner(
-    device: wgpu::Device,
-    queue: wgpu::Queue,
-    numbers: &[u32],
-) -> Option<Vec<u32>> {
-    let context = Context::new(device, queue);
-
+async fn execute_gpu_inner(context: Context, numbers: &[u32]) -> Option<Vec<u32>> {
     // Loads the shader from WGSL
     let cs_module = Shader::new(include_wgsl!("shader.wgsl"), &context);
 
@@ -132,6 +113,8 @@ async fn execute_gpu_inner(
                 bind_group_offsets: vec![vec![]],
                 pipeline: compute_pipeline,
                 extent: (numbers.len() as u32, 1, 1), // Number of cells to run, the (x,y,z) size of item being processed
+                indirect: None,
+                push_constant_data: vec![],
             });
         }
 
@@ -146,9 +129,9 @@ async fn execute_gpu_inner(
     slice.map_async(wgpu::MapMode::Read, move |v| sender.send(v).unwrap());
 
     // Poll the device in a blocking manner so that our future resolves.
-    // In an actual application, `device.poll(...)` should
+    // In an actual application, the device should be polled
     // be called in an event loop or on another thread.
-    context.device().poll(wgpu::Maintain::Wait);
+    context.wait_for_idle();
 
     // Awaits until `buffer_future` can be read from
     if let Some(Ok(())) = receiver.receive().await {
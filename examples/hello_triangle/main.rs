@@ -1,6 +1,6 @@
 use simple_wgpu::{
-    ColorAttachment, CommandEncoder, Context, DrawCall, RenderPipelineBuilder, RenderTexture,
-    Shader,
+    ColorAttachment, CommandEncoder, Context, DrawCallBuilder, RenderPipelineBuilder,
+    RenderTexture, Shader,
 };
 use wgpu::include_wgsl;
 use winit::{
@@ -109,16 +109,11 @@ async fn run(event_loop: EventLoop<()>, window: Window) {
                             None,
                         );
 
-                        rpass.draw(DrawCall {
-                            bind_groups: vec![],
-                            bind_group_offsets: vec![],
-                            pipeline: render_pipeline.clone(),
-                            vertices: vec![],
-                            indices: None,
-                            element_range: 0..3,
-                            instance_range: 0..1,
-                            rasteriser_state: Default::default(),
-                        });
+                        rpass.draw(
+                            DrawCallBuilder::new(&render_pipeline, vec![])
+                                .element_range(0..3)
+                                .build(),
+                        );
                     }
                 }
 
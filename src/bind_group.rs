@@ -1,4 +1,9 @@
-use std::{collections::HashMap, hash::Hash, num::NonZeroU64, sync::Arc};
+use std::{
+    collections::{HashMap, HashSet},
+    hash::Hash,
+    num::{NonZeroU32, NonZeroU64},
+    sync::Arc,
+};
 
 use crate::{buffer::BufferBinding, context::Context, sampler::Sampler, texture::TextureBinding};
 
@@ -16,6 +21,7 @@ pub(crate) struct Binding {
 pub(crate) enum BindingResource {
     Buffer(BufferBinding, Option<usize>),
     Texture(TextureBinding),
+    TextureArray(Vec<TextureBinding>),
     Sampler(Sampler),
 }
 
@@ -47,7 +53,7 @@ impl BindGroupLayout {
 ///  
 /// The equivalent to [wgpu::BindGroup]
 
-#[derive(Clone, Hash, PartialEq, Eq, Debug)]
+#[derive(Clone, Default, Hash, PartialEq, Eq, Debug)]
 pub struct BindGroup {
     bindings: Vec<Binding>,
     name: Option<String>,
@@ -71,6 +77,12 @@ impl BindGroup {
                     ty: texture.binding_type,
                     count: None,
                 },
+                BindingResource::TextureArray(textures) => wgpu::BindGroupLayoutEntry {
+                    binding: b.binding as u32,
+                    visibility: b.visibility,
+                    ty: textures[0].binding_type,
+                    count: NonZeroU32::new(textures.len() as u32),
+                },
                 BindingResource::Buffer(buffer, _) => wgpu::BindGroupLayoutEntry {
                     binding: b.binding as u32,
                     visibility: b.visibility,
@@ -95,6 +107,7 @@ impl BindGroup {
                 let gpu_layout = self.build_layout().get_or_build(context);
 
                 let mut texture_views = HashMap::new();
+                let mut texture_arrays = HashMap::new();
                 let mut samplers = HashMap::new();
 
                 for b in &self.bindings {
@@ -103,6 +116,15 @@ impl BindGroup {
                             texture_views
                                 .insert(&texture.texture, texture.texture.get_or_build(context));
                         }
+                        BindingResource::TextureArray(textures) => {
+                            texture_arrays.insert(
+                                b.binding,
+                                textures
+                                    .iter()
+                                    .map(|t| t.texture.get_or_build(context))
+                                    .collect::<Vec<_>>(),
+                            );
+                        }
                         BindingResource::Sampler(sampler) => {
                             samplers.insert(sampler, sampler.get_or_build(context));
                         }
@@ -110,6 +132,16 @@ impl BindGroup {
                     }
                 }
 
+                let texture_arrays = texture_arrays
+                    .iter()
+                    .map(|(binding, views)| {
+                        (
+                            *binding,
+                            views.iter().map(|v| v.as_ref()).collect::<Vec<_>>(),
+                        )
+                    })
+                    .collect::<HashMap<_, _>>();
+
                 let gpu_bindings = self
                     .bindings
                     .iter()
@@ -128,6 +160,12 @@ impl BindGroup {
                                 &texture_views.get(&texture.texture).unwrap(),
                             ),
                         },
+                        BindingResource::TextureArray(_) => wgpu::BindGroupEntry {
+                            binding: b.binding as u32,
+                            resource: wgpu::BindingResource::TextureViewArray(
+                                texture_arrays.get(&b.binding).unwrap(),
+                            ),
+                        },
                         BindingResource::Sampler(sampler) => wgpu::BindGroupEntry {
                             binding: b.binding as u32,
                             resource: wgpu::BindingResource::Sampler(
@@ -157,6 +195,12 @@ pub struct BindGroupBuilder {
     name: Option<String>,
 }
 
+impl Default for BindGroupBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl BindGroupBuilder {
     /// Create a new builder
     pub fn new() -> Self {
@@ -188,6 +232,45 @@ impl BindGroupBuilder {
         self
     }
 
+    /// Bind a [Buffer](crate::Buffer) to this bind group, overriding the minimum binding size
+    /// declared in the bind group layout
+    ///
+    /// `size` is the number of bytes bound, as for [BindGroupBuilder::buffer]. `min_binding_size`
+    /// lets wgpu validate the binding against the shader when the pipeline is created, rather
+    /// than at each draw call
+    pub fn buffer_with_min_size(
+        self,
+        binding: usize,
+        visibility: wgpu::ShaderStages,
+        buffer: &BufferBinding,
+        size: Option<usize>,
+        min_binding_size: Option<NonZeroU64>,
+    ) -> Self {
+        let mut buffer = buffer.clone();
+        buffer.min_binding_size = min_binding_size;
+        self.buffer(binding, visibility, &buffer, size)
+    }
+
+    /// Bind a [Buffer](crate::Buffer) to this bind group, with a dynamic offset
+    ///
+    /// Each use of the bind group binds `min_binding_size` bytes of the buffer, starting at the
+    /// offset supplied in [DrawCall::bind_group_offsets](crate::DrawCall::bind_group_offsets)
+    /// or [Dispatch::bind_group_offsets](crate::Dispatch::bind_group_offsets)
+    pub fn buffer_dynamic_offset(
+        self,
+        binding: usize,
+        visibility: wgpu::ShaderStages,
+        buffer: &BufferBinding,
+        min_binding_size: u64,
+    ) -> Self {
+        self.buffer(
+            binding,
+            visibility,
+            &buffer.clone().dynamic_offset(min_binding_size),
+            Some(min_binding_size as usize),
+        )
+    }
+
     /// Bind a [Texture](crate::Texture) to this bind group
     pub fn texture(
         mut self,
@@ -203,7 +286,37 @@ impl BindGroupBuilder {
         self
     }
 
+    /// Bind several [Textures](crate::Texture) to a single binding, as a `binding_array`
+    ///
+    /// All of the textures must be bound the same way. Requires
+    /// [Features::TEXTURE_BINDING_ARRAY](wgpu::Features::TEXTURE_BINDING_ARRAY)
+    pub fn texture_array(
+        mut self,
+        binding: usize,
+        visibility: wgpu::ShaderStages,
+        textures: &[TextureBinding],
+    ) -> Self {
+        assert!(!textures.is_empty(), "texture array must not be empty");
+        debug_assert!(
+            textures
+                .iter()
+                .all(|t| t.binding_type == textures[0].binding_type),
+            "all textures in a texture array must have the same binding type"
+        );
+
+        self.bindings.push(Binding {
+            binding,
+            visibility,
+            resource: BindingResource::TextureArray(textures.to_vec()),
+        });
+        self
+    }
+
     /// Bind a [Sampler](crate::Sampler) to this bind group
+    ///
+    /// The binding type is derived from the sampler: samplers created with
+    /// [SamplerBuilder::comparison](crate::SamplerBuilder::comparison) are bound as
+    /// [SamplerBindingType::Comparison](wgpu::SamplerBindingType::Comparison)
     pub fn sampler(
         mut self,
         binding: usize,
@@ -219,10 +332,85 @@ impl BindGroupBuilder {
     }
 
     /// Consume this builder and return a [BindGroup]
+    ///
+    /// Panics if the same binding index was used more than once
     pub fn build(self) -> BindGroup {
+        let mut seen = HashSet::new();
+        for b in &self.bindings {
+            assert!(
+                seen.insert(b.binding),
+                "binding index {} appears more than once in bind group",
+                b.binding
+            );
+        }
+
         BindGroup {
             bindings: self.bindings,
             name: self.name,
         }
     }
+
+    /// Consume this builder, and immediately create the underlying wgpu bind group
+    ///
+    /// Any validation errors are returned here, rather than surfacing the first time
+    /// the [BindGroup] is used
+    pub fn build_immediate(self, context: &Context) -> Result<BindGroup, wgpu::Error> {
+        let bind_group = self.build();
+
+        if let Err(error) = context.capture_validation_error(|| bind_group.get_or_build(context)) {
+            // don't leave invalid objects in the caches, to be handed out on the next lookup
+            let caches = context.caches();
+            caches.bind_group_cache.borrow_mut().remove(&bind_group);
+            caches
+                .bind_group_layout_cache
+                .borrow_mut()
+                .remove(&bind_group.build_layout());
+            return Err(error);
+        }
+
+        Ok(bind_group)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sampler::SamplerBuilder;
+
+    #[test]
+    fn distinct_binding_indices_are_accepted() {
+        let sampler = SamplerBuilder::new().build();
+
+        let bind_group = BindGroupBuilder::new()
+            .sampler(0, wgpu::ShaderStages::FRAGMENT, &sampler)
+            .sampler(1, wgpu::ShaderStages::FRAGMENT, &sampler)
+            .build();
+
+        assert_eq!(bind_group.bindings.len(), 2);
+    }
+
+    #[test]
+    #[should_panic(expected = "binding index 0 appears more than once in bind group")]
+    fn duplicate_sampler_binding_index_panics() {
+        let sampler = SamplerBuilder::new().build();
+
+        BindGroupBuilder::new()
+            .sampler(0, wgpu::ShaderStages::FRAGMENT, &sampler)
+            .sampler(0, wgpu::ShaderStages::FRAGMENT, &sampler)
+            .build();
+    }
+
+    #[test]
+    #[should_panic(expected = "binding index 1 appears more than once in bind group")]
+    fn duplicate_binding_index_among_others_panics() {
+        let linear = SamplerBuilder::new().linear().build();
+        let nearest = SamplerBuilder::new().nearest().build();
+
+        BindGroupBuilder::new()
+            .sampler(0, wgpu::ShaderStages::FRAGMENT, &linear)
+            .sampler(1, wgpu::ShaderStages::FRAGMENT, &linear)
+            .sampler(2, wgpu::ShaderStages::FRAGMENT, &nearest)
+            .sampler(1, wgpu::ShaderStages::VERTEX, &nearest)
+            .build();
+    }
 }
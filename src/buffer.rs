@@ -1,14 +1,15 @@
 use std::{
     hash::Hash,
+    marker::PhantomData,
     num::NonZeroU64,
-    ops::{Bound, Range, RangeBounds},
+    ops::{Bound, Deref, Range, RangeBounds},
     sync::Arc,
 };
 
 use uuid::Uuid;
 use wgpu::util::DeviceExt;
 
-use crate::context::Context;
+use crate::{command_encoder::CommandEncoder, context::Context, error::SimpleWgpuError};
 
 #[derive(Debug)]
 struct BufferInternal {
@@ -60,6 +61,76 @@ impl Buffer {
         }
     }
 
+    /// Create an empty buffer, that is mapped for writing on creation
+    ///
+    /// Populate the buffer with [Buffer::write_mapped]. The `size` must be a multiple of
+    /// [COPY_BUFFER_ALIGNMENT](wgpu::COPY_BUFFER_ALIGNMENT)
+    pub fn new_mapped(
+        label: wgpu::Label,
+        usage: wgpu::BufferUsages,
+        size: usize,
+        context: &Context,
+    ) -> Self {
+        let buffer = context.device().create_buffer(&wgpu::BufferDescriptor {
+            label,
+            usage,
+            size: size as u64,
+            mapped_at_creation: true,
+        });
+
+        Self {
+            id: Uuid::new_v4(),
+            data: Arc::new(BufferInternal {
+                buffer,
+                size,
+                usage,
+            }),
+        }
+    }
+
+    /// Wrap a buffer that was created directly through wgpu
+    pub fn from_wgpu(buffer: wgpu::Buffer) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            data: Arc::new(BufferInternal {
+                size: buffer.size() as usize,
+                usage: buffer.usage(),
+                buffer,
+            }),
+        }
+    }
+
+    /// Create a buffer with every byte set to zero
+    ///
+    /// The buffer is zeroed while mapped at creation, so no [CommandEncoder] is required.
+    /// The `size` must be a multiple of [COPY_BUFFER_ALIGNMENT](wgpu::COPY_BUFFER_ALIGNMENT)
+    pub fn create_zeroed(
+        label: wgpu::Label,
+        usage: wgpu::BufferUsages,
+        size: usize,
+        context: &Context,
+    ) -> Self {
+        let buffer = Self::new_mapped(label, usage, size, context);
+        buffer.data.buffer.slice(..).get_mapped_range_mut().fill(0);
+        buffer.unmap();
+        buffer
+    }
+
+    /// Create an empty buffer to hold the arguments for indirect draws or dispatches
+    ///
+    /// The buffer can be written from the CPU, or filled by a compute shader via
+    /// [Buffer::storage_binding]
+    pub fn new_indirect(label: wgpu::Label, size: usize, context: &Context) -> Self {
+        Self::new(
+            label,
+            wgpu::BufferUsages::INDIRECT
+                | wgpu::BufferUsages::STORAGE
+                | wgpu::BufferUsages::COPY_DST,
+            size,
+            context,
+        )
+    }
+
     /// Create a buffer and immediately upload data to it
     pub fn with_data(
         label: wgpu::Label,
@@ -86,6 +157,9 @@ impl Buffer {
     }
 
     /// Grow the buffer to `new_size`. Does nothing if the buffer is already larger than `new_size`
+    ///
+    /// Growing the buffer allocates a new GPU buffer, and gives this handle a new identity.
+    /// Any [BindGroup](crate::BindGroup) built from the old buffer must be rebuilt to see the new one
     pub fn ensure_capacity(&mut self, new_size: usize, context: &Context) {
         if new_size > self.data.size {
             Arc::get_mut(&mut self.data)
@@ -99,6 +173,55 @@ impl Buffer {
                     });
                 })
                 .expect("couldn't get exclusive access to resize buffer");
+            self.id = Uuid::new_v4();
+        }
+    }
+
+    /// Grow the buffer to `new_size`, preserving its existing contents
+    ///
+    /// Like [Buffer::ensure_capacity], but the old contents are copied into the new GPU buffer.
+    /// The copy is submitted to the GPU immediately, independent of any [CommandEncoder].
+    /// The buffer must have been created with both [BufferUsages::COPY_SRC](wgpu::BufferUsages::COPY_SRC)
+    /// and [BufferUsages::COPY_DST](wgpu::BufferUsages::COPY_DST), and its current size must be a
+    /// multiple of [COPY_BUFFER_ALIGNMENT](wgpu::COPY_BUFFER_ALIGNMENT), as buffer copies can't
+    /// include a partial trailing word
+    pub fn ensure_capacity_preserve(&mut self, new_size: usize, context: &Context) {
+        if new_size > self.data.size {
+            let required = wgpu::BufferUsages::COPY_SRC | wgpu::BufferUsages::COPY_DST;
+            assert!(
+                self.data.usage.contains(required),
+                "preserving buffer contents requires {:?}",
+                required
+            );
+            assert!(
+                self.data.size as u64 % wgpu::COPY_BUFFER_ALIGNMENT == 0,
+                "preserving buffer contents requires a size that is a multiple of {}, got {}",
+                wgpu::COPY_BUFFER_ALIGNMENT,
+                self.data.size
+            );
+
+            let data = Arc::get_mut(&mut self.data)
+                .expect("couldn't get exclusive access to resize buffer");
+
+            let buffer = context.device().create_buffer(&wgpu::BufferDescriptor {
+                label: None,
+                usage: data.usage,
+                size: new_size as u64,
+                mapped_at_creation: false,
+            });
+
+            let mut encoder =
+                context
+                    .device()
+                    .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                        label: Some("resize"),
+                    });
+            encoder.copy_buffer_to_buffer(&data.buffer, 0, &buffer, 0, data.size as u64);
+            context.queue().submit(Some(encoder.finish()));
+
+            data.size = new_size;
+            data.buffer = buffer;
+            self.id = Uuid::new_v4();
         }
     }
 
@@ -107,6 +230,158 @@ impl Buffer {
         context.queue().write_buffer(&self.data.buffer, 0, data);
     }
 
+    /// Write data to the buffer, starting at `offset` bytes
+    ///
+    /// Both `offset` and the length of `data` must be multiples of
+    /// [COPY_BUFFER_ALIGNMENT](wgpu::COPY_BUFFER_ALIGNMENT). Panics if the write
+    /// doesn't fit within the buffer
+    pub fn write_at(&self, offset: u64, data: &[u8], context: &Context) {
+        assert!(
+            offset + data.len() as u64 <= self.data.size as u64,
+            "write of {} bytes at offset {} is out of bounds for buffer of size {}",
+            data.len(),
+            offset,
+            self.data.size
+        );
+        assert!(
+            offset % wgpu::COPY_BUFFER_ALIGNMENT == 0
+                && data.len() as u64 % wgpu::COPY_BUFFER_ALIGNMENT == 0,
+            "offset and size must be multiples of {}",
+            wgpu::COPY_BUFFER_ALIGNMENT
+        );
+
+        context
+            .queue()
+            .write_buffer(&self.data.buffer, offset, data);
+    }
+
+    /// Read the contents of the buffer back to the CPU
+    ///
+    /// The buffer must have been created with [BufferUsages::COPY_SRC](wgpu::BufferUsages::COPY_SRC)
+    /// or [BufferUsages::MAP_READ](wgpu::BufferUsages::MAP_READ). Any necessary copy is submitted to
+    /// the GPU immediately, independent of any [CommandEncoder](crate::CommandEncoder),
+    /// and the returned future waits for all submitted work to complete
+    ///
+    /// With the `tokio` feature enabled, the wait happens on a blocking thread. Without it,
+    /// polling the future blocks the current thread until the GPU is done
+    pub async fn download(&self, context: &Context) -> Result<Vec<u8>, SimpleWgpuError> {
+        if self.data.usage.contains(wgpu::BufferUsages::MAP_READ) {
+            return read_staging_buffer(&self.data.buffer, context).await;
+        }
+
+        if !self.data.usage.contains(wgpu::BufferUsages::COPY_SRC) {
+            return Err(SimpleWgpuError::MissingBufferUsage(
+                wgpu::BufferUsages::COPY_SRC,
+            ));
+        }
+
+        let staging = context.device().create_buffer(&wgpu::BufferDescriptor {
+            label: Some("download"),
+            usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+            size: self.data.size as u64,
+            mapped_at_creation: false,
+        });
+
+        let mut encoder =
+            context
+                .device()
+                .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                    label: Some("download"),
+                });
+        encoder.copy_buffer_to_buffer(&self.data.buffer, 0, &staging, 0, self.data.size as u64);
+        context.queue().submit(Some(encoder.finish()));
+
+        read_staging_buffer(&staging, context).await
+    }
+
+    /// Read the contents of the buffer back to the CPU
+    ///
+    /// Like [Buffer::download], but panics if the buffer can't be read back
+    pub async fn read_back(&self, context: &Context) -> Vec<u8> {
+        self.download(context)
+            .await
+            .expect("failed to read back buffer")
+    }
+
+    /// Read the contents of the buffer back to the CPU, as an array of `T`
+    ///
+    /// Any trailing bytes that don't make up a whole `T` are discarded
+    pub async fn read_back_typed<T: bytemuck::Pod>(&self, context: &Context) -> Vec<T> {
+        self.read_back(context)
+            .await
+            .chunks_exact(std::mem::size_of::<T>())
+            .map(bytemuck::pod_read_unaligned)
+            .collect()
+    }
+
+    /// Record a copy of this buffer into a new staging buffer, which can be read back to the CPU
+    ///
+    /// Once `encoder` has been submitted, read the results with [Buffer::download]
+    pub fn readback_copy(&self, encoder: &mut CommandEncoder, context: &Context) -> Buffer {
+        let staging = Buffer::new(
+            Some("readback"),
+            wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+            self.size(),
+            context,
+        );
+
+        encoder.copy_buffer_to_buffer(self, 0, &staging, 0, self.size());
+
+        staging
+    }
+
+    /// Record a copy of the whole of this buffer to the start of `destination`
+    pub fn copy_to(&self, destination: &Buffer, encoder: &mut CommandEncoder) {
+        self.copy_range_to(0..self.size() as u64, destination, 0, encoder);
+    }
+
+    /// Record a copy of `src_range` bytes of this buffer into `destination`, starting at `dst_offset`
+    pub fn copy_range_to(
+        &self,
+        src_range: Range<u64>,
+        destination: &Buffer,
+        dst_offset: u64,
+        encoder: &mut CommandEncoder,
+    ) {
+        assert!(
+            src_range.start <= src_range.end && src_range.end <= self.size() as u64,
+            "source range {:?} is out of bounds for buffer of size {}",
+            src_range,
+            self.size()
+        );
+        let size = src_range.end - src_range.start;
+        assert!(
+            dst_offset + size <= destination.size() as u64,
+            "copying {} bytes to offset {} is out of bounds for buffer of size {}",
+            size,
+            dst_offset,
+            destination.size()
+        );
+
+        encoder.copy_buffer_to_buffer(
+            self,
+            src_range.start as usize,
+            destination,
+            dst_offset as usize,
+            size as usize,
+        );
+    }
+
+    /// The size of the buffer in bytes
+    pub fn size(&self) -> usize {
+        self.data.size
+    }
+
+    /// The usages this buffer was created with
+    pub fn usage(&self) -> wgpu::BufferUsages {
+        self.data.usage
+    }
+
+    /// A unique identifier for this buffer, suitable for use as a cache key
+    pub fn id(&self) -> Uuid {
+        self.id
+    }
+
     pub(crate) fn buffer(&self) -> &wgpu::Buffer {
         &self.data.buffer
     }
@@ -144,6 +419,22 @@ impl Buffer {
         }
     }
 
+    /// Write to a buffer created by [Buffer::new_mapped], and then unmap it
+    pub fn write_mapped<F: FnOnce(&mut [u8])>(&self, f: F) {
+        assert!(
+            self.data
+                .usage
+                .intersects(wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_WRITE),
+            "buffer must have COPY_DST or MAP_WRITE usage to be written while mapped"
+        );
+
+        {
+            let mut view = self.data.buffer.slice(..).get_mapped_range_mut();
+            f(&mut view);
+        }
+        self.data.buffer.unmap();
+    }
+
     /// See wgpu's [Buffer::unmap](wgpu::Buffer::unmap)
     pub fn unmap(&self) {
         self.data.buffer.unmap();
@@ -151,6 +442,7 @@ impl Buffer {
 }
 
 impl BufferBinding {
+    /// Bind with a dynamic offset, supplied when the bind group is used
     pub fn dynamic_offset(mut self, min_binding_size: u64) -> Self {
         self.has_dynamic_offset = true;
         self.min_binding_size = NonZeroU64::new(min_binding_size);
@@ -172,6 +464,97 @@ impl PartialEq for Buffer {
 
 impl Eq for Buffer {}
 
+/// A [Buffer] holding a single value of type `T`, for use as a uniform buffer
+#[derive(Clone, Debug)]
+pub struct UniformBuffer<T: bytemuck::Pod> {
+    buffer: Buffer,
+    _marker: PhantomData<T>,
+}
+
+impl<T: bytemuck::Pod> UniformBuffer<T> {
+    /// Create a uniform buffer, initialised with `data`
+    pub fn new(label: wgpu::Label, data: &T, context: &Context) -> Self {
+        Self {
+            buffer: Buffer::with_data(
+                label,
+                wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+                bytemuck::bytes_of(data),
+                context,
+            ),
+            _marker: PhantomData,
+        }
+    }
+
+    /// Replace the contents of the buffer
+    pub fn write(&self, data: &T, context: &Context) {
+        self.buffer.write(bytemuck::bytes_of(data), context);
+    }
+}
+
+impl<T: bytemuck::Pod> Deref for UniformBuffer<T> {
+    type Target = Buffer;
+
+    fn deref(&self) -> &Buffer {
+        &self.buffer
+    }
+}
+
+/// A [Buffer] holding an array of values of type `T`, for use as a storage buffer
+#[derive(Clone, Debug)]
+pub struct StorageBuffer<T: bytemuck::Pod> {
+    buffer: Buffer,
+    _marker: PhantomData<T>,
+}
+
+impl<T: bytemuck::Pod> StorageBuffer<T> {
+    /// Create a storage buffer, initialised with `data`
+    pub fn new(label: wgpu::Label, data: &[T], context: &Context) -> Self {
+        Self {
+            buffer: Buffer::with_data(
+                label,
+                wgpu::BufferUsages::STORAGE
+                    | wgpu::BufferUsages::COPY_DST
+                    | wgpu::BufferUsages::COPY_SRC,
+                bytemuck::cast_slice(data),
+                context,
+            ),
+            _marker: PhantomData,
+        }
+    }
+
+    /// The number of elements the buffer can hold
+    pub fn capacity(&self) -> usize {
+        self.buffer.size() / std::mem::size_of::<T>()
+    }
+
+    /// Write `data` to the start of the buffer. The buffer must have enough capacity to hold it
+    pub fn write_slice(&self, data: &[T], context: &Context) {
+        assert!(
+            data.len() <= self.capacity(),
+            "writing {} elements to a storage buffer with capacity {}",
+            data.len(),
+            self.capacity()
+        );
+        self.buffer.write(bytemuck::cast_slice(data), context);
+    }
+
+    /// Grow the buffer to hold at least `count` elements
+    ///
+    /// See [Buffer::ensure_capacity]
+    pub fn ensure_capacity(&mut self, count: usize, context: &Context) {
+        self.buffer
+            .ensure_capacity(count * std::mem::size_of::<T>(), context);
+    }
+}
+
+impl<T: bytemuck::Pod> Deref for StorageBuffer<T> {
+    type Target = Buffer;
+
+    fn deref(&self) -> &Buffer {
+        &self.buffer
+    }
+}
+
 /// A sub-slice of a [Buffer](Buffer)
 #[derive(Debug)]
 pub struct BufferSlice {
@@ -185,6 +568,78 @@ impl BufferSlice {
     pub fn get(&self) -> wgpu::BufferSlice {
         self.data.buffer.slice(self.bounds.clone())
     }
+
+    pub(crate) fn buffer(&self) -> &wgpu::Buffer {
+        &self.data.buffer
+    }
+
+    pub(crate) fn offset(&self) -> wgpu::BufferAddress {
+        self.bounds.start
+    }
+
+    /// Advance the start of the slice by `offset` bytes
+    pub(crate) fn skip(mut self, offset: wgpu::BufferAddress) -> Self {
+        assert!(
+            self.bounds.start + offset <= self.bounds.end,
+            "offset {} is out of bounds for buffer slice {:?}",
+            offset,
+            self.bounds
+        );
+        self.bounds.start += offset;
+        self
+    }
+
+    pub(crate) fn usage(&self) -> wgpu::BufferUsages {
+        self.data.usage
+    }
+
+    /// Catch slices that can't hold indirect arguments as soon as they are passed in
+    pub(crate) fn debug_assert_indirect(&self) {
+        debug_assert!(
+            self.usage().contains(wgpu::BufferUsages::INDIRECT),
+            "indirect arguments require a buffer with BufferUsages::INDIRECT"
+        );
+    }
+}
+
+/// Map a buffer that has had a copy submitted into it, and read back its contents
+pub(crate) async fn read_staging_buffer(
+    staging: &wgpu::Buffer,
+    context: &Context,
+) -> Result<Vec<u8>, SimpleWgpuError> {
+    let (sender, receiver) = std::sync::mpsc::channel();
+    let slice = staging.slice(..);
+    slice.map_async(wgpu::MapMode::Read, move |result| {
+        let _ = sender.send(result);
+    });
+
+    wait_for_mapping(context).await;
+
+    receiver
+        .recv()
+        .expect("buffer mapping callback was dropped")?;
+
+    let data = slice.get_mapped_range().to_vec();
+    staging.unmap();
+
+    Ok(data)
+}
+
+/// Block until submitted work, including pending buffer mappings, has completed
+///
+/// With the `tokio` feature enabled and a runtime available, the wait happens on a blocking
+/// thread, so the async executor isn't stalled. Otherwise the current thread blocks
+async fn wait_for_mapping(context: &Context) {
+    #[cfg(feature = "tokio")]
+    if tokio::runtime::Handle::try_current().is_ok() {
+        let device = context.ctx.device.clone();
+        tokio::task::spawn_blocking(move || device.poll(wgpu::Maintain::Wait))
+            .await
+            .expect("device poll panicked");
+        return;
+    }
+
+    context.device().poll(wgpu::Maintain::Wait);
 }
 
 fn constrain_range_to_container_len<R>(range: R, container_len: u64) -> Range<u64>
@@ -1,12 +1,21 @@
-use std::num::NonZeroU64;
+use std::{num::NonZeroU64, ops::Range};
+
+use wgpu::util::DeviceExt;
 
 use crate::{
+    bind_group::BindGroupBuilder,
     buffer::Buffer,
     compute_pass::ComputePass,
     context::Context,
     dispatch::Dispatch,
     draw_call::DrawCall,
-    render_pass::{ColorAttachment, DepthStencilAttachment, RenderPass},
+    error::SimpleWgpuError,
+    query_set::QuerySet,
+    render_pass::{ColorAttachment, DepthStencilAttachment, DrawStats, DynamicState, RenderPass},
+    render_pipeline::RenderPipelineBuilder,
+    render_texture::RenderTexture,
+    sampler::SamplerBuilder,
+    texture::Texture,
 };
 
 #[derive(Debug)]
@@ -17,9 +26,19 @@ pub(crate) enum Pass {
         depth_stencil_attachment: Option<DepthStencilAttachment>,
         multisample: Option<wgpu::MultisampleState>,
         draw_calls: Vec<DrawCall>,
+        dynamic_states: Vec<DynamicState>,
     },
     Compute(Option<String>, Vec<Dispatch>),
     ClearBuffer(Buffer, u64, Option<NonZeroU64>),
+    ClearTexture(Texture, wgpu::ImageSubresourceRange),
+    WriteBuffer(Buffer, u64, Vec<u8>),
+    WriteTimestamp(QuerySet, u32),
+    ResolveQuerySet {
+        query_set: QuerySet,
+        queries: Range<u32>,
+        destination: Buffer,
+        destination_offset: u64,
+    },
     CopyBufferToBuffer {
         source: Buffer,
         source_offset: usize,
@@ -27,6 +46,32 @@ pub(crate) enum Pass {
         destination_offset: usize,
         size: usize,
     },
+    CopyTextureToTexture {
+        source: Texture,
+        source_origin: wgpu::Origin3d,
+        destination: Texture,
+        destination_origin: wgpu::Origin3d,
+        size: wgpu::Extent3d,
+    },
+    CopyBufferToTexture {
+        source: Buffer,
+        layout: wgpu::ImageDataLayout,
+        destination: Texture,
+        destination_origin: wgpu::Origin3d,
+        size: wgpu::Extent3d,
+    },
+    CopyTextureToBuffer {
+        source: Texture,
+        source_origin: wgpu::Origin3d,
+        destination: Buffer,
+        layout: wgpu::ImageDataLayout,
+        size: wgpu::Extent3d,
+    },
+    /// Consecutive staging belt writes, recorded eagerly into a separate wgpu command encoder
+    StagingBeltWrites(Option<wgpu::CommandEncoder>),
+    DebugMarker(String),
+    PushDebugGroup(String),
+    PopDebugGroup,
 }
 
 /// Encodes a series of GPU operations
@@ -34,11 +79,49 @@ pub(crate) enum Pass {
 /// Accumulates render passes, compute passes, and GPU transfer commands.
 /// No work is submitted to the GPU until the command encoder is dropped.
 ///
+/// Passes are recorded in the order they are declared, into a single wgpu command buffer.
+/// Compute and render passes may be freely interleaved - wgpu inserts the necessary
+/// resource barriers, so a render pass can read the output of the compute pass
+/// recorded before it (and vice versa):
+///
+/// ```no_run
+/// # use simple_wgpu::{ColorAttachment, CommandEncoder, Context, Dispatch, DrawCall, RenderTexture};
+/// # fn example(context: &Context, simulate: Dispatch, draw: DrawCall, target: RenderTexture) {
+/// let mut encoder = CommandEncoder::new(Some("frame"), context);
+///
+/// {
+///     // writes a storage texture...
+///     let mut cpass = encoder.compute_pass(Some("simulate"));
+///     cpass.dispatch(simulate);
+/// }
+///
+/// {
+///     // ...which is then sampled while rendering
+///     let mut rpass = encoder.render_pass(
+///         Some("draw"),
+///         vec![ColorAttachment {
+///             target,
+///             resolve_target: None,
+///             ops: wgpu::Operations::default(),
+///         }],
+///         None,
+///         None,
+///     );
+///     rpass.draw(draw);
+/// }
+///
+/// // dropping the encoder submits both passes, in order
+/// drop(encoder);
+/// # }
+/// ```
+///
 /// This is more or less the equivalent to [wgpu::CommandEncoder]
 pub struct CommandEncoder {
     label: Option<String>,
     context: Context,
     pub(crate) passes: Vec<Pass>,
+    /// Whether anything has been submitted by [CommandEncoder::flush]
+    flushed: bool,
 }
 
 impl CommandEncoder {
@@ -47,6 +130,48 @@ impl CommandEncoder {
             label: label.map(|s| s.to_string()),
             context: context.clone(),
             passes: vec![],
+            flushed: false,
+        }
+    }
+
+    /// Create `n` independent command encoders
+    ///
+    /// Passes can be recorded into each encoder separately, and then recombined with
+    /// [CommandEncoder::merge], for a single submission to the GPU
+    ///
+    /// Note that this does not enable multi-threaded recording: command encoders share the
+    /// [Context]'s resource caches, which are not thread-safe, so encoders are not [Send] and
+    /// must all be recorded on the thread that created them
+    pub fn split(n: usize, context: &Context) -> Vec<CommandEncoder> {
+        (0..n).map(|_| Self::new(None, context)).collect()
+    }
+
+    /// Combine several command encoders into one
+    ///
+    /// The passes from each encoder are appended in order. The merged encoders are
+    /// consumed without submitting anything to the GPU. Returns `None` if `encoders` is empty
+    pub fn merge(encoders: Vec<CommandEncoder>) -> Option<CommandEncoder> {
+        let mut encoders = encoders.into_iter();
+        let mut merged = encoders.next()?;
+
+        for mut encoder in encoders {
+            // leaves the encoder empty, so dropping it submits nothing
+            merged.passes.append(&mut encoder.passes);
+            merged.flushed |= std::mem::take(&mut encoder.flushed);
+        }
+
+        Some(merged)
+    }
+
+    /// Submit everything recorded so far to the GPU, without dropping the encoder
+    ///
+    /// Anything recorded afterwards forms a new submission. The resource caches still only
+    /// age once, when the encoder is dropped, so flushing doesn't shorten the cache lifetimes
+    pub fn flush(&mut self) {
+        if !self.passes.is_empty() {
+            self.submit();
+            self.passes.clear();
+            self.flushed = true;
         }
     }
 
@@ -77,6 +202,120 @@ impl CommandEncoder {
             .push(Pass::ClearBuffer(buffer.clone(), offset, size));
     }
 
+    /// Clear the given subresources of a texture to zero
+    ///
+    /// Requires [Features::CLEAR_TEXTURE](wgpu::Features::CLEAR_TEXTURE)
+    pub fn clear_texture(
+        &mut self,
+        texture: &Texture,
+        subresource_range: wgpu::ImageSubresourceRange,
+    ) {
+        self.passes
+            .push(Pass::ClearTexture(texture.clone(), subresource_range));
+    }
+
+    /// Write `data` into a buffer, starting at `offset` bytes
+    ///
+    /// The data is staged in a temporary buffer and copied into `destination` in recording
+    /// order, so the write sees the results of earlier passes, and later passes see the write.
+    /// Both `offset` and the length of `data` must be multiples of
+    /// [COPY_BUFFER_ALIGNMENT](wgpu::COPY_BUFFER_ALIGNMENT)
+    pub fn write_buffer_with_offset(&mut self, destination: &Buffer, offset: u64, data: &[u8]) {
+        assert!(
+            offset % wgpu::COPY_BUFFER_ALIGNMENT == 0
+                && data.len() as u64 % wgpu::COPY_BUFFER_ALIGNMENT == 0,
+            "offset and size must be multiples of {}",
+            wgpu::COPY_BUFFER_ALIGNMENT
+        );
+
+        self.passes.push(Pass::WriteBuffer(
+            destination.clone(),
+            offset,
+            data.to_vec(),
+        ));
+    }
+
+    /// Write a GPU timestamp into `query_set` at `index`, once all previous passes have completed
+    ///
+    /// Requires [Features::TIMESTAMP_QUERY](wgpu::Features::TIMESTAMP_QUERY). Recording a timestamp
+    /// before and after a pass measures its duration on the GPU
+    pub fn write_timestamp(&mut self, query_set: &QuerySet, index: u32) {
+        self.passes
+            .push(Pass::WriteTimestamp(query_set.clone(), index));
+    }
+
+    /// Copy the results of `queries` into `destination`, as one `u64` per query
+    ///
+    /// Multiply the difference between two timestamps by [Context::timestamp_period] to get
+    /// nanoseconds. The buffer requires [BufferUsages::QUERY_RESOLVE](wgpu::BufferUsages::QUERY_RESOLVE),
+    /// and `destination_offset` must be a multiple of [QUERY_RESOLVE_BUFFER_ALIGNMENT](wgpu::QUERY_RESOLVE_BUFFER_ALIGNMENT)
+    pub fn resolve_query_set(
+        &mut self,
+        query_set: &QuerySet,
+        queries: Range<u32>,
+        destination: &Buffer,
+        destination_offset: u64,
+    ) {
+        self.passes.push(Pass::ResolveQuerySet {
+            query_set: query_set.clone(),
+            queries,
+            destination: destination.clone(),
+            destination_offset,
+        });
+    }
+
+    /// Write `data` into a buffer, starting at `offset` bytes, via a staging belt
+    ///
+    /// Unlike [CommandEncoder::write_buffer_with_offset], the data is copied straight into the
+    /// belt's mapped staging memory. Call [CommandEncoder::finish_staging_belt] once all writes
+    /// are recorded, and [StagingBelt::recall](wgpu::util::StagingBelt::recall) after this
+    /// encoder has been submitted
+    ///
+    /// Both `offset` and the length of `data` must be multiples of
+    /// [COPY_BUFFER_ALIGNMENT](wgpu::COPY_BUFFER_ALIGNMENT)
+    pub fn write_buffer_with_staging_belt(
+        &mut self,
+        belt: &mut wgpu::util::StagingBelt,
+        destination: &Buffer,
+        offset: u64,
+        data: &[u8],
+    ) {
+        assert!(
+            offset % wgpu::COPY_BUFFER_ALIGNMENT == 0
+                && data.len() as u64 % wgpu::COPY_BUFFER_ALIGNMENT == 0,
+            "offset and size must be multiples of {}",
+            wgpu::COPY_BUFFER_ALIGNMENT
+        );
+
+        let Some(size) = NonZeroU64::new(data.len() as u64) else {
+            return;
+        };
+
+        let device = self.context.device();
+
+        // consecutive writes share a single wgpu encoder
+        if !matches!(self.passes.last(), Some(Pass::StagingBeltWrites(Some(_)))) {
+            let encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some("staging belt"),
+            });
+            self.passes.push(Pass::StagingBeltWrites(Some(encoder)));
+        }
+        let Some(Pass::StagingBeltWrites(Some(encoder))) = self.passes.last_mut() else {
+            unreachable!();
+        };
+
+        belt.write_buffer(encoder, destination.buffer(), offset, size, device)
+            .copy_from_slice(data);
+    }
+
+    /// Unmap the staging belt's buffers, so that writes recorded with
+    /// [CommandEncoder::write_buffer_with_staging_belt] can be submitted
+    ///
+    /// Must be called before this encoder is submitted
+    pub fn finish_staging_belt(&mut self, belt: &mut wgpu::util::StagingBelt) {
+        belt.finish();
+    }
+
     pub fn copy_buffer_to_buffer(
         &mut self,
         source: &Buffer,
@@ -94,16 +333,246 @@ impl CommandEncoder {
         });
     }
 
+    /// Copy a region of one texture to another
+    ///
+    /// The copy reads from and writes to the first mip level of each [Texture] view
+    pub fn copy_texture_to_texture(
+        &mut self,
+        source: &Texture,
+        source_origin: wgpu::Origin3d,
+        destination: &Texture,
+        destination_origin: wgpu::Origin3d,
+        size: wgpu::Extent3d,
+    ) {
+        self.passes.push(Pass::CopyTextureToTexture {
+            source: source.clone(),
+            source_origin,
+            destination: destination.clone(),
+            destination_origin,
+            size,
+        });
+    }
+
+    /// Copy data from a buffer into a region of a texture
+    ///
+    /// The copy writes to the first mip level of the [Texture] view
+    pub fn copy_buffer_to_texture(
+        &mut self,
+        source: &Buffer,
+        layout: wgpu::ImageDataLayout,
+        destination: &Texture,
+        destination_origin: wgpu::Origin3d,
+        size: wgpu::Extent3d,
+    ) {
+        self.passes.push(Pass::CopyBufferToTexture {
+            source: source.clone(),
+            layout,
+            destination: destination.clone(),
+            destination_origin,
+            size,
+        });
+    }
+
+    /// Copy a region of a texture into a buffer
+    ///
+    /// The copy reads from the first mip level of the [Texture] view
+    pub fn copy_texture_to_buffer(
+        &mut self,
+        source: &Texture,
+        source_origin: wgpu::Origin3d,
+        destination: &Buffer,
+        layout: wgpu::ImageDataLayout,
+        size: wgpu::Extent3d,
+    ) {
+        self.passes.push(Pass::CopyTextureToBuffer {
+            source: source.clone(),
+            source_origin,
+            destination: destination.clone(),
+            layout,
+            size,
+        });
+    }
+
+    /// Insert a debug marker, which will show up in GPU profilers and debuggers
+    pub fn insert_debug_marker(&mut self, label: &str) {
+        self.passes.push(Pass::DebugMarker(label.to_string()));
+    }
+
+    /// Begin a labelled group of passes, which will show up in GPU profilers and debuggers
+    ///
+    /// Must be matched by a call to [CommandEncoder::pop_debug_group]
+    pub fn push_debug_group(&mut self, label: &str) {
+        self.passes.push(Pass::PushDebugGroup(label.to_string()));
+    }
+
+    /// End the group started by the most recent [CommandEncoder::push_debug_group]
+    pub fn pop_debug_group(&mut self) {
+        self.passes.push(Pass::PopDebugGroup);
+    }
+
+    /// Fill in every mip level of `texture` by successively downsampling the first
+    ///
+    /// Records one render pass per generated mip level. Only 2D textures with a single
+    /// array layer and a single sample, with a filterable float format that can also be rendered
+    /// to, are supported, and the texture must have
+    /// both [TEXTURE_BINDING](wgpu::TextureUsages::TEXTURE_BINDING) and
+    /// [RENDER_ATTACHMENT](wgpu::TextureUsages::RENDER_ATTACHMENT) usages
+    pub fn generate_mipmaps(&mut self, texture: &Texture) -> Result<(), SimpleWgpuError> {
+        if texture.dimension() != wgpu::TextureDimension::D2
+            || texture.size().depth_or_array_layers != 1
+        {
+            return Err(SimpleWgpuError::UnsupportedTextureDimension(
+                texture.dimension(),
+            ));
+        }
+
+        if texture.sample_type() != (wgpu::TextureSampleType::Float { filterable: true }) {
+            return Err(SimpleWgpuError::UnsupportedTextureFormat(texture.format()));
+        }
+
+        check_blit_target(texture, &self.context)?;
+
+        let required_usage =
+            wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::RENDER_ATTACHMENT;
+        if !texture.usage().contains(required_usage) {
+            return Err(SimpleWgpuError::MissingTextureUsage(required_usage));
+        }
+
+        let context = self.context.clone();
+
+        let mips = texture.mip_range();
+        for mip in mips.start + 1..mips.end {
+            self.blit(
+                "mipmap blit",
+                &texture.view(mip - 1, 1),
+                texture.as_render_texture_mip(mip - mips.start, &context),
+            );
+        }
+
+        Ok(())
+    }
+
+    /// Copy `source` to `destination`, scaling and converting the format as needed
+    ///
+    /// Records a render pass that samples the first mip level of `source`. The source must
+    /// have a filterable float format and [TEXTURE_BINDING](wgpu::TextureUsages::TEXTURE_BINDING)
+    /// usage. The destination must have a renderable float format, a single sample, and
+    /// [RENDER_ATTACHMENT](wgpu::TextureUsages::RENDER_ATTACHMENT) usage. For same-format
+    /// copies, prefer [CommandEncoder::copy_texture_to_texture]
+    pub fn blit_texture(
+        &mut self,
+        source: &Texture,
+        destination: &Texture,
+    ) -> Result<(), SimpleWgpuError> {
+        if source.sample_type() != (wgpu::TextureSampleType::Float { filterable: true }) {
+            return Err(SimpleWgpuError::UnsupportedTextureFormat(source.format()));
+        }
+
+        if !source
+            .usage()
+            .contains(wgpu::TextureUsages::TEXTURE_BINDING)
+        {
+            return Err(SimpleWgpuError::MissingTextureUsage(
+                wgpu::TextureUsages::TEXTURE_BINDING,
+            ));
+        }
+
+        check_blit_target(destination, &self.context)?;
+
+        if !destination
+            .usage()
+            .contains(wgpu::TextureUsages::RENDER_ATTACHMENT)
+        {
+            return Err(SimpleWgpuError::MissingTextureUsage(
+                wgpu::TextureUsages::RENDER_ATTACHMENT,
+            ));
+        }
+
+        let context = self.context.clone();
+
+        self.blit(
+            "blit",
+            &source.view(source.mip_range().start, 1),
+            destination.as_render_texture(&context),
+        );
+
+        Ok(())
+    }
+
+    /// Record a render pass that draws `source` over the whole of `target`
+    fn blit(&mut self, label: &str, source: &Texture, target: RenderTexture) {
+        let context = self.context.clone();
+        let shader = context.blit_shader();
+
+        // the pipeline is cached per target format, like any other render pipeline
+        let pipeline = RenderPipelineBuilder::with_vertex(&shader.entry_point("vs_main"), [])
+            .fragment_with_defaults(&shader.entry_point("fs_main"))
+            .label(label)
+            .build();
+
+        let sampler = SamplerBuilder::new().clamp().linear().build();
+
+        let bind_group = BindGroupBuilder::new()
+            .texture(0, wgpu::ShaderStages::FRAGMENT, &source.texture_binding())
+            .sampler(1, wgpu::ShaderStages::FRAGMENT, &sampler)
+            .build();
+
+        let mut render_pass = self.render_pass(
+            Some(label),
+            vec![ColorAttachment {
+                target,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(wgpu::Color::TRANSPARENT),
+                    store: true,
+                },
+            }],
+            None,
+            None,
+        );
+
+        render_pass.draw(DrawCall {
+            bind_groups: vec![bind_group],
+            bind_group_offsets: vec![vec![]],
+            pipeline,
+            vertices: vec![],
+            indices: None,
+            index_format: wgpu::IndexFormat::Uint16,
+            indirect: None,
+            element_range: 0..3,
+            instance_range: 0..1,
+            base_vertex: 0,
+            instance_base: 0,
+            push_constant_data: vec![],
+            rasteriser_state: None,
+        });
+    }
+
+    /// The number of passes and transfer commands recorded so far
+    pub fn pass_count(&self) -> usize {
+        self.passes.len()
+    }
+
+    /// Statistics for the draw calls in all render passes recorded so far
+    pub fn total_stats(&self) -> DrawStats {
+        self.passes
+            .iter()
+            .filter_map(|p| match p {
+                Pass::Render { draw_calls, .. } => Some(DrawStats::from_draw_calls(draw_calls)),
+                _ => None,
+            })
+            .fold(DrawStats::default(), |total, stats| total + stats)
+    }
+
     /// Consumes the frame and flushes all pending operations to the GPU
     fn submit(&mut self) {
-        let mut encoder =
-            self.context
-                .device()
-                .create_command_encoder(&wgpu::CommandEncoderDescriptor {
-                    label: self.label.as_deref(),
-                });
-
-        for p in &self.passes {
+        let descriptor = wgpu::CommandEncoderDescriptor {
+            label: self.label.as_deref(),
+        };
+        let mut encoder = self.context.device().create_command_encoder(&descriptor);
+        let mut command_buffers = vec![];
+
+        for p in &mut self.passes {
             match p {
                 Pass::Render {
                     label,
@@ -111,12 +580,14 @@ impl CommandEncoder {
                     depth_stencil_attachment,
                     multisample,
                     draw_calls,
+                    dynamic_states,
                 } => Self::record_render_pass(
                     label,
                     color_attachments,
                     depth_stencil_attachment,
                     multisample,
                     draw_calls,
+                    dynamic_states,
                     &mut encoder,
                     &self.context,
                 ),
@@ -126,6 +597,41 @@ impl CommandEncoder {
                 Pass::ClearBuffer(buffer, offset, size) => {
                     encoder.clear_buffer(buffer.buffer(), *offset, *size)
                 }
+                Pass::ClearTexture(texture, subresource_range) => {
+                    encoder.clear_texture(texture.raw(), subresource_range)
+                }
+                Pass::WriteTimestamp(query_set, index) => {
+                    encoder.write_timestamp(&query_set.query_set, *index)
+                }
+                Pass::ResolveQuerySet {
+                    query_set,
+                    queries,
+                    destination,
+                    destination_offset,
+                } => encoder.resolve_query_set(
+                    &query_set.query_set,
+                    queries.clone(),
+                    destination.buffer(),
+                    *destination_offset,
+                ),
+                Pass::WriteBuffer(buffer, offset, data) => {
+                    if !data.is_empty() {
+                        let staging = self.context.device().create_buffer_init(
+                            &wgpu::util::BufferInitDescriptor {
+                                label: Some("write buffer"),
+                                contents: data,
+                                usage: wgpu::BufferUsages::COPY_SRC,
+                            },
+                        );
+                        encoder.copy_buffer_to_buffer(
+                            &staging,
+                            0,
+                            buffer.buffer(),
+                            *offset,
+                            data.len() as u64,
+                        );
+                    }
+                }
                 Pass::CopyBufferToBuffer {
                     source,
                     source_offset,
@@ -139,12 +645,62 @@ impl CommandEncoder {
                     *destination_offset as u64,
                     *size as u64,
                 ),
+                Pass::CopyTextureToTexture {
+                    source,
+                    source_origin,
+                    destination,
+                    destination_origin,
+                    size,
+                } => encoder.copy_texture_to_texture(
+                    source.as_image_copy(*source_origin),
+                    destination.as_image_copy(*destination_origin),
+                    *size,
+                ),
+                Pass::CopyBufferToTexture {
+                    source,
+                    layout,
+                    destination,
+                    destination_origin,
+                    size,
+                } => encoder.copy_buffer_to_texture(
+                    wgpu::ImageCopyBuffer {
+                        buffer: source.buffer(),
+                        layout: *layout,
+                    },
+                    destination.as_image_copy(*destination_origin),
+                    *size,
+                ),
+                Pass::CopyTextureToBuffer {
+                    source,
+                    source_origin,
+                    destination,
+                    layout,
+                    size,
+                } => encoder.copy_texture_to_buffer(
+                    source.as_image_copy(*source_origin),
+                    wgpu::ImageCopyBuffer {
+                        buffer: destination.buffer(),
+                        layout: *layout,
+                    },
+                    *size,
+                ),
+                Pass::DebugMarker(label) => encoder.insert_debug_marker(label),
+                Pass::PushDebugGroup(label) => encoder.push_debug_group(label),
+                Pass::PopDebugGroup => encoder.pop_debug_group(),
+                Pass::StagingBeltWrites(belt_encoder) => {
+                    // finish the commands recorded so far, so that ordering is preserved
+                    let previous = std::mem::replace(
+                        &mut encoder,
+                        self.context.device().create_command_encoder(&descriptor),
+                    );
+                    command_buffers.push(previous.finish());
+                    command_buffers.extend(belt_encoder.take().map(|e| e.finish()));
+                }
             }
         }
 
-        self.context.queue().submit(Some(encoder.finish()));
-
-        self.context.caches().age();
+        command_buffers.push(encoder.finish());
+        self.context.queue().submit(command_buffers);
     }
 
     fn record_compute_pass(
@@ -188,8 +744,16 @@ impl CommandEncoder {
 
             compute_pass.set_pipeline(&pipelines[i]);
 
-            let (x, y, z) = dispatch.extent;
-            compute_pass.dispatch_workgroups(x, y, z);
+            for (offset, data) in &dispatch.push_constant_data {
+                compute_pass.set_push_constants(*offset, data);
+            }
+
+            if let Some(indirect) = &dispatch.indirect {
+                compute_pass.dispatch_workgroups_indirect(indirect.buffer(), indirect.offset());
+            } else {
+                let (x, y, z) = dispatch.extent;
+                compute_pass.dispatch_workgroups(x, y, z);
+            }
         }
     }
 
@@ -199,6 +763,7 @@ impl CommandEncoder {
         depth_stencil_attachment: &Option<DepthStencilAttachment>,
         multisample: &Option<wgpu::MultisampleState>,
         draw_calls: &Vec<DrawCall>,
+        dynamic_states: &[DynamicState],
         encoder: &mut wgpu::CommandEncoder,
         context: &Context,
     ) {
@@ -225,7 +790,7 @@ impl CommandEncoder {
                     &color_formats,
                     depth_stencil_attachment.as_ref().map(|d| d.target.format),
                     multisample,
-                    &draw_call.rasteriser_state,
+                    &draw_call.rasteriser_state.clone().unwrap_or_default(),
                     &draw_call.bind_groups,
                     context,
                 )
@@ -266,7 +831,32 @@ impl CommandEncoder {
         };
         let mut render_pass = encoder.begin_render_pass(&desc);
 
+        let mut current_state = DynamicState::default();
+        let mut stencil_reference = 0;
+
         for (index, draw_call) in draw_calls.iter().enumerate() {
+            let state = &dynamic_states[index];
+
+            if state.viewport != current_state.viewport {
+                if let Some(v) = state.viewport {
+                    render_pass.set_viewport(v.x, v.y, v.w, v.h, v.min_depth, v.max_depth);
+                }
+            }
+
+            if state.scissor_rect != current_state.scissor_rect {
+                if let Some((x, y, w, h)) = state.scissor_rect {
+                    render_pass.set_scissor_rect(x, y, w, h);
+                }
+            }
+
+            if state.blend_constant != current_state.blend_constant {
+                if let Some(color) = state.blend_constant {
+                    render_pass.set_blend_constant(color);
+                }
+            }
+
+            current_state = *state;
+
             for j in 0..draw_call.bind_groups.len() {
                 render_pass.set_bind_group(
                     j as u32,
@@ -277,30 +867,86 @@ impl CommandEncoder {
 
             render_pass.set_pipeline(&pipelines[index]);
 
+            let reference = draw_call
+                .rasteriser_state
+                .as_ref()
+                .and_then(|r| r.stencil_reference)
+                .or(state.stencil_reference)
+                .unwrap_or(0);
+            if reference != stencil_reference {
+                render_pass.set_stencil_reference(reference);
+                stencil_reference = reference;
+            }
+
+            for (stages, offset, data) in &draw_call.push_constant_data {
+                render_pass.set_push_constants(*stages, *offset, data);
+            }
+
             for (idx, buffer_slice) in draw_call.vertices.iter().enumerate() {
                 render_pass.set_vertex_buffer(idx as u32, buffer_slice.get());
             }
 
             if let Some(buffer_slice) = &draw_call.indices {
-                render_pass.set_index_buffer(buffer_slice.get(), wgpu::IndexFormat::Uint16);
+                render_pass.set_index_buffer(buffer_slice.get(), draw_call.index_format);
+            }
 
-                render_pass.draw_indexed(
+            let instances = draw_call.instance_base + draw_call.instance_range.start as u32
+                ..draw_call.instance_base + draw_call.instance_range.end as u32;
+
+            match (&draw_call.indices, &draw_call.indirect) {
+                (Some(_), Some(indirect)) => {
+                    render_pass.draw_indexed_indirect(indirect.buffer(), indirect.offset());
+                }
+                (None, Some(indirect)) => {
+                    render_pass.draw_indirect(indirect.buffer(), indirect.offset());
+                }
+                (Some(_), None) => render_pass.draw_indexed(
                     draw_call.element_range.start as u32..draw_call.element_range.end as u32,
-                    0,
-                    draw_call.instance_range.start as u32..draw_call.instance_range.end as u32,
-                );
-            } else {
-                render_pass.draw(
+                    draw_call.base_vertex,
+                    instances,
+                ),
+                (None, None) => render_pass.draw(
                     draw_call.element_range.start as u32..draw_call.element_range.end as u32,
-                    draw_call.instance_range.start as u32..draw_call.instance_range.end as u32,
-                );
+                    instances,
+                ),
             }
         }
     }
 }
 
+/// The blit shader writes a single `vec4<f32>`, so targets must be single-sampled,
+/// renderable, float color formats
+fn check_blit_target(texture: &Texture, context: &Context) -> Result<(), SimpleWgpuError> {
+    let format = texture.format();
+    let features = context.features();
+
+    let renderable = features.contains(wgpu::Features::TEXTURE_ADAPTER_SPECIFIC_FORMAT_FEATURES)
+        || format
+            .guaranteed_format_features(features)
+            .allowed_usages
+            .contains(wgpu::TextureUsages::RENDER_ATTACHMENT);
+
+    if !format.has_color_aspect()
+        || !matches!(texture.sample_type(), wgpu::TextureSampleType::Float { .. })
+        || texture.raw().sample_count() != 1
+        || !renderable
+    {
+        return Err(SimpleWgpuError::UnsupportedTextureFormat(format));
+    }
+
+    Ok(())
+}
+
 impl Drop for CommandEncoder {
     fn drop(&mut self) {
-        self.submit();
+        let submitting = !self.passes.is_empty();
+        if submitting {
+            self.submit();
+        }
+
+        // each encoder is one frame, however many times it was flushed
+        if submitting || self.flushed {
+            self.context.caches().age();
+        }
     }
 }
@@ -25,6 +25,9 @@ impl<'a> ComputePass<'a> {
 
     /// Dispatch a compute operation
     pub fn dispatch(&mut self, dispatch: Dispatch) {
+        if let Some(indirect) = &dispatch.indirect {
+            indirect.debug_assert_indirect();
+        }
         self.dispatches.push(dispatch)
     }
 }
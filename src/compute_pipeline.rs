@@ -10,6 +10,7 @@ use crate::{
 #[derive(Clone, Debug)]
 pub struct ComputePipeline {
     entry_point: EntryPoint,
+    push_constant_ranges: Vec<wgpu::PushConstantRange>,
     label: Option<String>,
 }
 
@@ -20,14 +21,37 @@ pub(crate) struct ComputePipelineCacheKey {
 }
 
 impl ComputePipeline {
+    /// The wgpu [BindGroupLayout](wgpu::BindGroupLayout) at `index`, when this pipeline is used
+    /// with `bind_groups`
+    ///
+    /// This is the same cached layout object the pipeline itself is built with, so it is
+    /// compatible with bind groups created outside of simple-wgpu. Returns `None` if `index`
+    /// is out of range
+    pub fn get_bind_group_layout(
+        &self,
+        index: u32,
+        bind_groups: &[BindGroup],
+        context: &Context,
+    ) -> Option<Arc<wgpu::BindGroupLayout>> {
+        self.layout(bind_groups)
+            .bind_group_layouts
+            .get(index as usize)
+            .map(|layout| layout.get_or_build(context))
+    }
+
+    fn layout(&self, bind_groups: &[BindGroup]) -> PipelineLayout {
+        PipelineLayout {
+            bind_group_layouts: bind_groups.iter().map(|b| b.build_layout()).collect(),
+            push_constant_ranges: self.push_constant_ranges.clone(),
+        }
+    }
+
     pub(crate) fn get_or_build(
         &self,
         context: &Context,
         bind_groups: &[BindGroup],
     ) -> Arc<wgpu::ComputePipeline> {
-        let layout = PipelineLayout {
-            bind_group_layouts: bind_groups.iter().map(|b| b.build_layout()).collect(),
-        };
+        let layout = self.layout(bind_groups);
 
         let key = ComputePipelineCacheKey {
             layout: layout.clone(),
@@ -38,6 +62,9 @@ impl ComputePipeline {
 
         pipeline_cache
             .get_or_insert_with(key, || {
+                #[cfg(all(debug_assertions, feature = "entry-point-validation"))]
+                self.entry_point.validate(naga::ShaderStage::Compute);
+
                 let layout = layout.get_or_build(context);
 
                 Arc::new(context.device().create_compute_pipeline(
@@ -57,6 +84,7 @@ impl ComputePipeline {
 #[derive(Clone)]
 pub struct ComputePipelineBuilder {
     entry_point: EntryPoint,
+    push_constant_ranges: Vec<wgpu::PushConstantRange>,
     label: Option<String>,
 }
 
@@ -64,10 +92,19 @@ impl ComputePipelineBuilder {
     pub fn with_entry_point(entry_point: &EntryPoint) -> Self {
         Self {
             entry_point: entry_point.clone(),
+            push_constant_ranges: vec![],
             label: None,
         }
     }
 
+    /// Declare the push constant ranges used by the shader in this pipeline
+    ///
+    /// Requires [Features::PUSH_CONSTANTS](wgpu::Features::PUSH_CONSTANTS)
+    pub fn push_constants(mut self, ranges: Vec<wgpu::PushConstantRange>) -> Self {
+        self.push_constant_ranges = ranges;
+        self
+    }
+
     /// Set the optional debug name. This may appear in error messages and GPU profiler traces
     pub fn label(mut self, label: &str) -> Self {
         self.label = Some(label.into());
@@ -77,6 +114,7 @@ impl ComputePipelineBuilder {
     pub fn build(self) -> ComputePipeline {
         ComputePipeline {
             entry_point: self.entry_point,
+            push_constant_ranges: self.push_constant_ranges,
             label: self.label,
         }
     }
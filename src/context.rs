@@ -1,28 +1,136 @@
-use std::{cell::RefCell, sync::Arc};
+use std::{
+    cell::{OnceCell, RefCell},
+    future::Future,
+    pin::pin,
+    sync::Arc,
+    task::{Poll, RawWaker, RawWakerVTable, Waker},
+};
 
 use crate::{
     bind_group::{BindGroup, BindGroupLayout},
     compute_pipeline::ComputePipelineCacheKey,
-    keyed_cache::KeyedCache,
+    keyed_cache::{CacheStats, KeyedCache},
     pipeline_layout::PipelineLayout,
+    query_set::QuerySet,
     render_pipeline::RenderPipelineCacheKey,
     sampler::Sampler,
+    shader::Shader,
     texture::Texture,
 };
 
-pub(crate) struct Caches {
-    pub bind_group_layout_cache: RefCell<KeyedCache<BindGroupLayout, Arc<wgpu::BindGroupLayout>>>,
-    pub bind_group_cache: RefCell<KeyedCache<BindGroup, Arc<wgpu::BindGroup>>>,
-    pub texture_view_cache: RefCell<KeyedCache<Texture, Arc<wgpu::TextureView>>>,
-    pub sampler_cache: RefCell<KeyedCache<Sampler, Arc<wgpu::Sampler>>>,
-    pub pipeline_layout_cache: RefCell<KeyedCache<PipelineLayout, Arc<wgpu::PipelineLayout>>>,
-    pub render_pipeline_cache:
+/// The resource caches owned by a [Context]
+pub struct Caches {
+    pub(crate) bind_group_layout_cache:
+        RefCell<KeyedCache<BindGroupLayout, Arc<wgpu::BindGroupLayout>>>,
+    pub(crate) bind_group_cache: RefCell<KeyedCache<BindGroup, Arc<wgpu::BindGroup>>>,
+    pub(crate) texture_view_cache: RefCell<KeyedCache<Texture, Arc<wgpu::TextureView>>>,
+    pub(crate) sampler_cache: RefCell<KeyedCache<Sampler, Arc<wgpu::Sampler>>>,
+    pub(crate) pipeline_layout_cache:
+        RefCell<KeyedCache<PipelineLayout, Arc<wgpu::PipelineLayout>>>,
+    pub(crate) render_pipeline_cache:
         RefCell<KeyedCache<RenderPipelineCacheKey, Arc<wgpu::RenderPipeline>>>,
-    pub compute_pipeline_cache:
+    pub(crate) compute_pipeline_cache:
         RefCell<KeyedCache<ComputePipelineCacheKey, Arc<wgpu::ComputePipeline>>>,
 }
 
+/// Options for creating a [Context] with [Context::from_instance]
+#[derive(Clone)]
+pub struct ContextOptions<'a> {
+    /// The surface the adapter must be able to present to, if any
+    pub compatible_surface: Option<&'a wgpu::Surface>,
+    /// Whether to prefer a low power or a high performance adapter
+    pub power_preference: wgpu::PowerPreference,
+    /// Features the device must support
+    pub required_features: wgpu::Features,
+    /// Limits the device must support
+    pub required_limits: wgpu::Limits,
+}
+
+impl<'a> Default for ContextOptions<'a> {
+    fn default() -> Self {
+        Self {
+            compatible_surface: None,
+            power_preference: wgpu::PowerPreference::default(),
+            required_features: wgpu::Features::empty(),
+            required_limits: wgpu::Limits::downlevel_defaults(),
+        }
+    }
+}
+
+/// Configures the resource caches owned by a [Context]
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct CacheConfig {
+    /// The number of frames a resource may go unused before it is evicted
+    pub max_age: usize,
+}
+
+impl Default for CacheConfig {
+    fn default() -> Self {
+        Self { max_age: 60 }
+    }
+}
+
+/// A snapshot of the statistics for every cache owned by a [Context]
+#[derive(Clone, Copy, Default, PartialEq, Eq, Hash, Debug)]
+pub struct AllCacheStats {
+    pub bind_group_layouts: CacheStats,
+    pub bind_groups: CacheStats,
+    pub texture_views: CacheStats,
+    pub samplers: CacheStats,
+    pub pipeline_layouts: CacheStats,
+    pub render_pipelines: CacheStats,
+    pub compute_pipelines: CacheStats,
+}
+
 impl Caches {
+    fn new(config: CacheConfig) -> Self {
+        Self {
+            bind_group_layout_cache: RefCell::new(KeyedCache::with_max_age(config.max_age)),
+            bind_group_cache: RefCell::new(KeyedCache::with_max_age(config.max_age)),
+            texture_view_cache: RefCell::new(KeyedCache::with_max_age(config.max_age)),
+            sampler_cache: RefCell::new(KeyedCache::with_max_age(config.max_age)),
+            pipeline_layout_cache: RefCell::new(KeyedCache::with_max_age(config.max_age)),
+            render_pipeline_cache: RefCell::new(KeyedCache::with_max_age(config.max_age)),
+            compute_pipeline_cache: RefCell::new(KeyedCache::with_max_age(config.max_age)),
+        }
+    }
+
+    /// Change the configuration of all caches. Takes effect the next time the caches are aged
+    pub fn configure(&self, config: CacheConfig) {
+        self.bind_group_layout_cache
+            .borrow_mut()
+            .set_max_age(config.max_age);
+        self.bind_group_cache
+            .borrow_mut()
+            .set_max_age(config.max_age);
+        self.texture_view_cache
+            .borrow_mut()
+            .set_max_age(config.max_age);
+        self.sampler_cache.borrow_mut().set_max_age(config.max_age);
+        self.pipeline_layout_cache
+            .borrow_mut()
+            .set_max_age(config.max_age);
+        self.render_pipeline_cache
+            .borrow_mut()
+            .set_max_age(config.max_age);
+        self.compute_pipeline_cache
+            .borrow_mut()
+            .set_max_age(config.max_age);
+    }
+
+    /// Collect the statistics from all caches
+    pub fn stats(&self) -> AllCacheStats {
+        AllCacheStats {
+            bind_group_layouts: self.bind_group_layout_cache.borrow().last_frame_stats(),
+            bind_groups: self.bind_group_cache.borrow().last_frame_stats(),
+            texture_views: self.texture_view_cache.borrow().last_frame_stats(),
+            samplers: self.sampler_cache.borrow().last_frame_stats(),
+            pipeline_layouts: self.pipeline_layout_cache.borrow().last_frame_stats(),
+            render_pipelines: self.render_pipeline_cache.borrow().last_frame_stats(),
+            compute_pipelines: self.compute_pipeline_cache.borrow().last_frame_stats(),
+        }
+    }
+
     pub(crate) fn age(&self) {
         self.bind_group_layout_cache.borrow_mut().age();
         self.bind_group_cache.borrow_mut().age();
@@ -35,9 +143,11 @@ impl Caches {
 }
 
 pub(crate) struct PrivateContext {
-    pub(crate) device: wgpu::Device,
+    pub(crate) device: Arc<wgpu::Device>,
     pub(crate) queue: wgpu::Queue,
     pub(crate) caches: Caches,
+    adapter_info: Option<wgpu::AdapterInfo>,
+    blit_shader: OnceCell<Shader>,
 }
 
 /// Wraps the wgpu [Device](wgpu::Device) and [Queue](wgpu::Queue), and caches all of the wgpu resource types
@@ -49,20 +159,66 @@ pub struct Context {
 impl Context {
     /// Create a context from the wgpu [Device](wgpu::Device) and [Queue](wgpu::Queue)
     pub fn new(device: wgpu::Device, queue: wgpu::Queue) -> Self {
-        let caches = Caches {
-            bind_group_layout_cache: RefCell::new(KeyedCache::new()),
-            bind_group_cache: RefCell::new(KeyedCache::new()),
-            texture_view_cache: RefCell::new(KeyedCache::new()),
-            sampler_cache: RefCell::new(KeyedCache::new()),
-            pipeline_layout_cache: RefCell::new(KeyedCache::new()),
-            render_pipeline_cache: RefCell::new(KeyedCache::new()),
-            compute_pipeline_cache: RefCell::new(KeyedCache::new()),
-        };
+        Self::create(device, queue, None)
+    }
+
+    /// Create a context from the wgpu [Device](wgpu::Device) and [Queue](wgpu::Queue),
+    /// recording information about the [Adapter](wgpu::Adapter) they were created from
+    pub fn with_adapter_info(
+        device: wgpu::Device,
+        queue: wgpu::Queue,
+        adapter_info: wgpu::AdapterInfo,
+    ) -> Self {
+        Self::create(device, queue, Some(adapter_info))
+    }
+
+    /// Select an adapter and create a device from `instance`, and wrap them in a context
+    ///
+    /// Returns `None` if no suitable adapter is found, or the device can't be created
+    pub async fn from_instance(
+        instance: &wgpu::Instance,
+        options: ContextOptions<'_>,
+    ) -> Option<Self> {
+        let adapter = instance
+            .request_adapter(&wgpu::RequestAdapterOptions {
+                power_preference: options.power_preference,
+                force_fallback_adapter: false,
+                compatible_surface: options.compatible_surface,
+            })
+            .await?;
+
+        let (device, queue) = adapter
+            .request_device(
+                &wgpu::DeviceDescriptor {
+                    label: None,
+                    features: options.required_features,
+                    limits: options.required_limits,
+                },
+                None,
+            )
+            .await
+            .map_err(|error| log::warn!("failed to create device: {}", error))
+            .ok()?;
+
+        Some(Self::with_adapter_info(device, queue, adapter.get_info()))
+    }
+
+    fn create(
+        device: wgpu::Device,
+        queue: wgpu::Queue,
+        adapter_info: Option<wgpu::AdapterInfo>,
+    ) -> Self {
+        #[cfg(debug_assertions)]
+        device.on_uncaptured_error(Box::new(|error| {
+            panic!("uncaptured wgpu error: {}", error);
+        }));
 
         let ctx = PrivateContext {
-            device,
+            device: Arc::new(device),
             queue,
-            caches,
+            caches: Caches::new(CacheConfig::default()),
+            adapter_info,
+            blit_shader: OnceCell::new(),
         };
 
         Self { ctx: Arc::new(ctx) }
@@ -76,7 +232,150 @@ impl Context {
         &self.ctx.queue
     }
 
-    pub(crate) fn caches(&self) -> &Caches {
+    /// Handle GPU errors that aren't captured by an error scope
+    ///
+    /// Replaces the default handler, which panics in debug builds
+    pub fn on_uncaptured_error(&self, handler: impl Fn(wgpu::Error) + Send + 'static) {
+        self.device().on_uncaptured_error(Box::new(handler));
+    }
+
+    /// The limits of the device
+    pub fn limits(&self) -> wgpu::Limits {
+        self.device().limits()
+    }
+
+    /// The features enabled on the device
+    pub fn features(&self) -> wgpu::Features {
+        self.device().features()
+    }
+
+    /// Information about the adapter the device was created from
+    ///
+    /// Only available if the context was created with [Context::with_adapter_info]
+    pub fn adapter_info(&self) -> Option<wgpu::AdapterInfo> {
+        self.ctx.adapter_info.clone()
+    }
+
+    /// Create a set of `count` timestamp queries
+    ///
+    /// Requires [Features::TIMESTAMP_QUERY](wgpu::Features::TIMESTAMP_QUERY)
+    pub fn create_query_set(&self, count: u32) -> QuerySet {
+        QuerySet::new(
+            self.device().create_query_set(&wgpu::QuerySetDescriptor {
+                label: None,
+                ty: wgpu::QueryType::Timestamp,
+                count,
+            }),
+            count,
+        )
+    }
+
+    /// The number of nanoseconds per timestamp tick
+    pub fn timestamp_period(&self) -> f32 {
+        self.queue().get_timestamp_period()
+    }
+
+    /// Create a staging belt for streaming data into buffers, allocating staging memory
+    /// in chunks of `chunk_size` bytes
+    ///
+    /// See [CommandEncoder::write_buffer_with_staging_belt](crate::CommandEncoder::write_buffer_with_staging_belt)
+    pub fn create_staging_belt(&self, chunk_size: u64) -> wgpu::util::StagingBelt {
+        wgpu::util::StagingBelt::new(chunk_size)
+    }
+
+    /// The resource caches owned by this context
+    pub fn caches(&self) -> &Caches {
         &self.ctx.caches
     }
+
+    /// Collect the statistics from all caches owned by this context
+    pub fn cache_stats(&self) -> AllCacheStats {
+        self.caches().stats()
+    }
+
+    /// A shader that copies a texture to a fullscreen render target
+    pub(crate) fn blit_shader(&self) -> &Shader {
+        self.ctx
+            .blit_shader
+            .get_or_init(|| Shader::new(wgpu::include_wgsl!("blit.wgsl"), self))
+    }
+
+    /// Block until all submitted work has completed
+    ///
+    /// With the `tokio` feature enabled and called from a multi-threaded runtime, this lets
+    /// the runtime move other tasks off the current worker thread while waiting. Prefer
+    /// [Context::wait_for_idle_async] from async code
+    pub fn wait_for_idle(&self) {
+        #[cfg(feature = "tokio")]
+        if let Ok(handle) = tokio::runtime::Handle::try_current() {
+            // block_in_place panics on a current_thread runtime
+            if handle.runtime_flavor() == tokio::runtime::RuntimeFlavor::MultiThread {
+                tokio::task::block_in_place(|| self.device().poll(wgpu::Maintain::Wait));
+                return;
+            }
+        }
+
+        self.device().poll(wgpu::Maintain::Wait);
+    }
+
+    /// Wait until all submitted work has completed, without blocking the current thread
+    ///
+    /// The device is polled once each time the returned future is polled, yielding to
+    /// the executor in between
+    pub async fn wait_for_idle_async(&self) {
+        while !self.device().poll(wgpu::Maintain::Poll) {
+            YieldNow(false).await;
+        }
+    }
+
+    /// Run `f` inside a validation error scope, and return the first error it raised
+    ///
+    /// On native backends errors are reported synchronously. On the web, where
+    /// errors are only reported asynchronously, this will never return an error
+    pub(crate) fn capture_validation_error<T, F: FnOnce() -> T>(
+        &self,
+        f: F,
+    ) -> Result<T, wgpu::Error> {
+        self.device()
+            .push_error_scope(wgpu::ErrorFilter::Validation);
+        let result = f();
+        let waker = noop_waker();
+        let error =
+            pin!(self.device().pop_error_scope()).poll(&mut std::task::Context::from_waker(&waker));
+
+        match error {
+            Poll::Ready(Some(error)) => Err(error),
+            _ => Ok(result),
+        }
+    }
+}
+
+/// A waker that does nothing, for polling futures which are expected to already be ready
+fn noop_waker() -> Waker {
+    const VTABLE: RawWakerVTable = RawWakerVTable::new(
+        |_| RawWaker::new(std::ptr::null(), &VTABLE),
+        |_| {},
+        |_| {},
+        |_| {},
+    );
+
+    // SAFETY: none of the vtable functions touch the data pointer
+    unsafe { Waker::from_raw(RawWaker::new(std::ptr::null(), &VTABLE)) }
+}
+
+/// Returns `Pending` once, so that an async loop gives other tasks a chance to run
+struct YieldNow(bool);
+
+impl Future for YieldNow {
+    type Output = ();
+
+    fn poll(mut self: std::pin::Pin<&mut Self>, cx: &mut std::task::Context<'_>) -> Poll<()> {
+        if self.0 {
+            Poll::Ready(())
+        } else {
+            self.0 = true;
+            cx.waker().wake_by_ref();
+            Poll::Pending
+        }
+    }
 }
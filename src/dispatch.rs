@@ -1,10 +1,77 @@
-use crate::{bind_group::BindGroup, compute_pipeline::ComputePipeline};
+use crate::{bind_group::BindGroup, buffer::BufferSlice, compute_pipeline::ComputePipeline};
 
 /// All of the data needed to issue a single compute operation
 #[derive(Debug)]
 pub struct Dispatch {
     pub bind_groups: Vec<BindGroup>,
+    /// The dynamic offsets for each bind group
+    ///
+    /// There must be one entry per bind group, containing one offset for each binding created with
+    /// [BindGroupBuilder::buffer_dynamic_offset](crate::BindGroupBuilder::buffer_dynamic_offset),
+    /// in binding order
     pub bind_group_offsets: Vec<Vec<u32>>,
     pub pipeline: ComputePipeline,
+    /// The number of workgroups to dispatch. Ignored if `indirect` is set
     pub extent: (u32, u32, u32),
+    /// Read the workgroup counts from a GPU buffer, instead of `extent`
+    ///
+    /// The slice must start at the arguments to use, laid out as three `u32` values.
+    /// The buffer requires [BufferUsages::INDIRECT](wgpu::BufferUsages::INDIRECT)
+    pub indirect: Option<BufferSlice>,
+    /// Push constant data, as `(offset, data)`
+    ///
+    /// Must fall within the push constant ranges declared on the pipeline
+    pub push_constant_data: Vec<(u32, Vec<u8>)>,
+}
+
+/// Builds a [Dispatch]
+pub struct DispatchBuilder {
+    dispatch: Dispatch,
+}
+
+impl DispatchBuilder {
+    /// Dispatch `extent` workgroups of `pipeline`
+    pub fn new(pipeline: &ComputePipeline, extent: (u32, u32, u32)) -> Self {
+        Self {
+            dispatch: Dispatch {
+                bind_groups: vec![],
+                bind_group_offsets: vec![],
+                pipeline: pipeline.clone(),
+                extent,
+                indirect: None,
+                push_constant_data: vec![],
+            },
+        }
+    }
+
+    /// Bind the next bind group slot
+    pub fn bind_group(self, bind_group: &BindGroup) -> Self {
+        self.bind_group_with_offsets(bind_group, vec![])
+    }
+
+    /// Bind the next bind group slot, with dynamic offsets
+    pub fn bind_group_with_offsets(mut self, bind_group: &BindGroup, offsets: Vec<u32>) -> Self {
+        self.dispatch.bind_groups.push(bind_group.clone());
+        self.dispatch.bind_group_offsets.push(offsets);
+        self
+    }
+
+    /// Read the workgroup counts from a GPU buffer. See [Dispatch::indirect]
+    pub fn indirect(mut self, indirect: BufferSlice) -> Self {
+        indirect.debug_assert_indirect();
+        self.dispatch.indirect = Some(indirect);
+        self
+    }
+
+    /// Add push constant data. See [Dispatch::push_constant_data]
+    pub fn push_constants(mut self, offset: u32, data: &[u8]) -> Self {
+        self.dispatch
+            .push_constant_data
+            .push((offset, data.to_vec()));
+        self
+    }
+
+    pub fn build(self) -> Dispatch {
+        self.dispatch
+    }
 }
@@ -1,15 +1,37 @@
-use std::ops::Range;
+use std::{hash::Hash, ops::Range};
 
 use crate::{bind_group::BindGroup, buffer::BufferSlice, render_pipeline::RenderPipeline};
 
 /// The set of rendering state that is convenient to vary on a per-draw basis
-#[derive(Debug, Clone, Hash, PartialEq, Eq)]
+#[derive(Debug, Clone)]
 pub struct RasteriserState {
     pub front_face: wgpu::FrontFace,
     pub cull_mode: Option<wgpu::Face>,
     pub depth_write: bool,
     pub depth_compare: wgpu::CompareFunction,
     pub polygon_mode: wgpu::PolygonMode,
+    /// Override the primitive topology set on the [RenderPipeline]
+    ///
+    /// `None` uses the pipeline's topology, which defaults to
+    /// [PrimitiveTopology::TriangleList](wgpu::PrimitiveTopology::TriangleList)
+    pub topology: Option<wgpu::PrimitiveTopology>,
+    /// Constant depth bias applied to each fragment. Ignored if the render pass has no depth attachment
+    pub depth_bias: i32,
+    /// Depth bias that scales with the slope of the primitive
+    pub depth_bias_slope_scale: f32,
+    /// The maximum depth bias, or 0.0 for no clamping
+    ///
+    /// A non-zero clamp requires [Features::DEPTH_BIAS_CLAMP](wgpu::Features::DEPTH_BIAS_CLAMP).
+    /// If the feature isn't enabled, the clamp is disabled instead
+    pub depth_bias_clamp: f32,
+    /// Stencil test and operations. Ignored if the render pass has no depth/stencil attachment
+    pub stencil: wgpu::StencilState,
+    /// The reference value used by the stencil test and [StencilOperation::Replace](wgpu::StencilOperation::Replace)
+    ///
+    /// `None` uses the value set with [RenderPass::set_stencil_reference](crate::RenderPass::set_stencil_reference),
+    /// or 0 if none was set. This is dynamic state, so unlike the other fields, changing it
+    /// doesn't require a new pipeline
+    pub stencil_reference: Option<u32>,
 }
 
 impl Default for RasteriserState {
@@ -20,14 +42,59 @@ impl Default for RasteriserState {
             depth_write: true,
             depth_compare: wgpu::CompareFunction::LessEqual,
             polygon_mode: wgpu::PolygonMode::Fill,
+            topology: None,
+            depth_bias: 0,
+            depth_bias_slope_scale: 0.0,
+            depth_bias_clamp: 0.0,
+            stencil: Default::default(),
+            stencil_reference: None,
         }
     }
 }
 
+// stencil_reference is set dynamically on the render pass, so it is left out of Hash and Eq
+// to avoid building a separate pipeline for each reference value
+impl Hash for RasteriserState {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.front_face.hash(state);
+        self.cull_mode.hash(state);
+        self.depth_write.hash(state);
+        self.depth_compare.hash(state);
+        self.polygon_mode.hash(state);
+        self.topology.hash(state);
+        self.depth_bias.hash(state);
+        self.depth_bias_slope_scale.to_bits().hash(state);
+        self.depth_bias_clamp.to_bits().hash(state);
+        self.stencil.hash(state);
+    }
+}
+
+impl PartialEq for RasteriserState {
+    fn eq(&self, other: &Self) -> bool {
+        self.front_face == other.front_face
+            && self.cull_mode == other.cull_mode
+            && self.depth_write == other.depth_write
+            && self.depth_compare == other.depth_compare
+            && self.polygon_mode == other.polygon_mode
+            && self.topology == other.topology
+            && self.depth_bias == other.depth_bias
+            && self.depth_bias_slope_scale.to_bits() == other.depth_bias_slope_scale.to_bits()
+            && self.depth_bias_clamp.to_bits() == other.depth_bias_clamp.to_bits()
+            && self.stencil == other.stencil
+    }
+}
+
+impl Eq for RasteriserState {}
+
 /// All of the data needed to issue a single draw call
 #[derive(Debug)]
 pub struct DrawCall {
     pub bind_groups: Vec<BindGroup>,
+    /// The dynamic offsets for each bind group
+    ///
+    /// There must be one entry per bind group, containing one offset for each binding created with
+    /// [BindGroupBuilder::buffer_dynamic_offset](crate::BindGroupBuilder::buffer_dynamic_offset),
+    /// in binding order
     pub bind_group_offsets: Vec<Vec<u32>>,
     pub pipeline: RenderPipeline,
     /// The vertex buffers, if any
@@ -38,12 +105,176 @@ pub struct DrawCall {
     ///
     /// If `indices` is `None`, the mesh data will be treated as unindexed
     pub indices: Option<BufferSlice>,
-    /// The range of vertices to draw
+    /// The format of the index buffer. Ignored if `indices` is `None`
+    ///
+    /// Use [IndexFormat::Uint32](wgpu::IndexFormat::Uint32) for meshes with more than 65535 vertices
+    pub index_format: wgpu::IndexFormat,
+    /// Read the draw arguments from a GPU buffer, instead of `element_range` and `instance_range`
+    ///
+    /// The slice must start at the arguments to use, laid out as [DrawIndirect](wgpu::util::DrawIndirect),
+    /// or as [DrawIndexedIndirect](wgpu::util::DrawIndexedIndirect) if `indices` is set.
+    /// The buffer requires [BufferUsages::INDIRECT](wgpu::BufferUsages::INDIRECT)
+    pub indirect: Option<BufferSlice>,
+    /// The range of vertices to draw. Ignored if `indirect` is set
     pub element_range: Range<usize>,
-    /// The range of instances to draw
+    /// The range of instances to draw. Ignored if `indirect` is set
     ///
     /// You can pass `0..1` to disable instancing
     pub instance_range: Range<usize>,
+    /// Added to each index before fetching vertices. Ignored for unindexed and indirect draws
+    ///
+    /// Pass `0` to use the indices as-is
+    pub base_vertex: i32,
+    /// Added to `instance_range`, to select where instance data starts in the instance buffers
+    ///
+    /// Ignored for indirect draws
+    pub instance_base: u32,
+    /// Push constant data, as `(stages, offset, data)`
+    ///
+    /// Must fall within the push constant ranges declared on the pipeline
+    pub push_constant_data: Vec<(wgpu::ShaderStages, u32, Vec<u8>)>,
     /// Additional state that is convenient to vary on a per-draw basis
-    pub rasteriser_state: RasteriserState,
+    ///
+    /// `None` is equivalent to [RasteriserState::default()]
+    pub rasteriser_state: Option<RasteriserState>,
+}
+
+/// Builds a [DrawCall]
+///
+/// Unset fields default to an unindexed, non-instanced draw with no bind groups
+pub struct DrawCallBuilder {
+    draw_call: DrawCall,
+}
+
+impl DrawCallBuilder {
+    /// Draw `vertices` with `pipeline`
+    pub fn new(pipeline: &RenderPipeline, vertices: Vec<BufferSlice>) -> Self {
+        Self {
+            draw_call: DrawCall {
+                bind_groups: vec![],
+                bind_group_offsets: vec![],
+                pipeline: pipeline.clone(),
+                vertices,
+                indices: None,
+                index_format: wgpu::IndexFormat::Uint16,
+                indirect: None,
+                element_range: 0..0,
+                instance_range: 0..1,
+                base_vertex: 0,
+                instance_base: 0,
+                push_constant_data: vec![],
+                rasteriser_state: None,
+            },
+        }
+    }
+
+    /// Bind the next bind group slot
+    pub fn bind_group(self, bind_group: &BindGroup) -> Self {
+        self.bind_group_with_offsets(bind_group, vec![])
+    }
+
+    /// Bind the next bind group slot, with dynamic offsets
+    pub fn bind_group_with_offsets(mut self, bind_group: &BindGroup, offsets: Vec<u32>) -> Self {
+        self.draw_call.bind_groups.push(bind_group.clone());
+        self.draw_call.bind_group_offsets.push(offsets);
+        self
+    }
+
+    /// Draw indexed geometry
+    pub fn indices(mut self, indices: BufferSlice, format: wgpu::IndexFormat) -> Self {
+        self.draw_call.indices = Some(indices);
+        self.draw_call.index_format = format;
+        self
+    }
+
+    /// Read the draw arguments from a GPU buffer. See [DrawCall::indirect]
+    pub fn indirect(mut self, indirect: BufferSlice) -> Self {
+        indirect.debug_assert_indirect();
+        self.draw_call.indirect = Some(indirect);
+        self
+    }
+
+    /// Set the range of vertices (or indices) to draw. See [DrawCall::element_range]
+    pub fn element_range(mut self, range: Range<usize>) -> Self {
+        self.draw_call.element_range = range;
+        self
+    }
+
+    /// Set the range of instances to draw. Defaults to `0..1`
+    pub fn instance_range(mut self, range: Range<usize>) -> Self {
+        self.draw_call.instance_range = range;
+        self
+    }
+
+    /// Set the value added to each index. See [DrawCall::base_vertex]
+    pub fn base_vertex(mut self, base_vertex: i32) -> Self {
+        self.draw_call.base_vertex = base_vertex;
+        self
+    }
+
+    /// Set where instance data starts. See [DrawCall::instance_base]
+    pub fn instance_base(mut self, instance_base: u32) -> Self {
+        self.draw_call.instance_base = instance_base;
+        self
+    }
+
+    /// Add push constant data. See [DrawCall::push_constant_data]
+    pub fn push_constants(mut self, stages: wgpu::ShaderStages, offset: u32, data: &[u8]) -> Self {
+        self.draw_call
+            .push_constant_data
+            .push((stages, offset, data.to_vec()));
+        self
+    }
+
+    /// Override the per-draw state. See [RasteriserState]
+    pub fn rasteriser_state(mut self, rasteriser_state: RasteriserState) -> Self {
+        self.draw_call.rasteriser_state = Some(rasteriser_state);
+        self
+    }
+
+    /// Finish building the draw call
+    pub fn build(self) -> DrawCall {
+        self.draw_call
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::hash_map::DefaultHasher, hash::Hasher};
+
+    use super::*;
+
+    fn hash_of(state: &RasteriserState) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        state.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    #[test]
+    fn stencil_reference_is_ignored_by_hash_and_eq() {
+        let a = RasteriserState::default();
+        let b = RasteriserState {
+            stencil_reference: Some(7),
+            ..Default::default()
+        };
+
+        assert_eq!(a, b);
+        assert_eq!(hash_of(&a), hash_of(&b));
+    }
+
+    #[test]
+    fn pipeline_state_is_compared() {
+        let a = RasteriserState::default();
+        let b = RasteriserState {
+            depth_bias_slope_scale: 1.5,
+            ..Default::default()
+        };
+        let c = RasteriserState {
+            topology: Some(wgpu::PrimitiveTopology::LineList),
+            ..Default::default()
+        };
+
+        assert_ne!(a, b);
+        assert_ne!(a, c);
+    }
 }
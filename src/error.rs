@@ -0,0 +1,54 @@
+use std::fmt;
+
+/// Errors that can be returned by simple-wgpu operations
+#[derive(Debug)]
+pub enum SimpleWgpuError {
+    /// The resource wasn't created with the usage flags the operation requires
+    MissingBufferUsage(wgpu::BufferUsages),
+    /// The resource wasn't created with the usage flags the operation requires
+    MissingTextureUsage(wgpu::TextureUsages),
+    /// The operation doesn't support textures of this format
+    UnsupportedTextureFormat(wgpu::TextureFormat),
+    /// The operation doesn't support textures of this dimension, or with multiple array layers
+    UnsupportedTextureDimension(wgpu::TextureDimension),
+    /// The requested mip level doesn't exist in the texture
+    MipLevelOutOfRange(u32),
+    /// Mapping a buffer for reading or writing failed
+    BufferAsync(wgpu::BufferAsyncError),
+}
+
+impl fmt::Display for SimpleWgpuError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::MissingBufferUsage(usage) => {
+                write!(f, "buffer is missing required usage {usage:?}")
+            }
+            Self::MissingTextureUsage(usage) => {
+                write!(f, "texture is missing required usage {usage:?}")
+            }
+            Self::UnsupportedTextureFormat(format) => {
+                write!(f, "unsupported texture format {format:?}")
+            }
+            Self::UnsupportedTextureDimension(dimension) => {
+                write!(f, "unsupported texture dimension {dimension:?}")
+            }
+            Self::MipLevelOutOfRange(mip) => write!(f, "mip level {mip} is out of range"),
+            Self::BufferAsync(error) => write!(f, "failed to map buffer: {error}"),
+        }
+    }
+}
+
+impl std::error::Error for SimpleWgpuError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::BufferAsync(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<wgpu::BufferAsyncError> for SimpleWgpuError {
+    fn from(error: wgpu::BufferAsyncError) -> Self {
+        Self::BufferAsync(error)
+    }
+}
@@ -1,25 +1,50 @@
 use std::{cell::Cell, collections::HashMap, hash::Hash};
 
+/// Hit/miss statistics for a single resource cache
+///
+/// Statistics are collected over a single frame (i.e. the lifetime of a single
+/// [CommandEncoder](crate::CommandEncoder))
+#[derive(Clone, Copy, Default, PartialEq, Eq, Hash, Debug)]
+pub struct CacheStats {
+    /// The number of lookups that found an existing resource
+    pub hits: usize,
+    /// The number of lookups that had to create a new resource
+    pub misses: usize,
+    /// The number of resources currently held by the cache
+    pub entries: usize,
+}
+
 pub struct KeyedCache<K, V>
 where
     K: Eq + Hash + Clone,
 {
     storage: HashMap<K, (usize, V)>,
     generation: usize,
+    max_age: usize,
     queries: Cell<usize>,
     misses: Cell<usize>,
+    last_stats: CacheStats,
 }
 
 impl<K, V> KeyedCache<K, V>
 where
     K: Eq + Hash + Clone,
 {
+    /// Create a cache that evicts entries which haven't been used for 60 frames
+    #[allow(dead_code)]
     pub fn new() -> Self {
+        Self::with_max_age(60)
+    }
+
+    /// Create a cache that evicts entries which haven't been used for `max_age` frames
+    pub fn with_max_age(max_age: usize) -> Self {
         Self {
             storage: HashMap::new(),
             generation: 0,
+            max_age,
             queries: Cell::new(0),
             misses: Cell::new(0),
+            last_stats: CacheStats::default(),
         }
     }
 
@@ -37,19 +62,108 @@ where
         v
     }
 
+    /// Drop the entry for `key`, if any
+    pub fn remove(&mut self, key: &K) {
+        self.storage.remove(key);
+    }
+
     pub fn age(&mut self) {
         self.generation += 1;
 
         self.storage
-            .retain(|_, (age, _)| *age + 60 > self.generation);
+            .retain(|_, (age, _)| *age + self.max_age > self.generation);
 
         let queries = self.queries.get();
         let misses = self.misses.get();
-        let _hits = queries - misses;
 
-        // debug!("cache stats: {hits} hits, {misses} misses");
+        self.last_stats = CacheStats {
+            hits: queries - misses,
+            misses,
+            entries: self.storage.len(),
+        };
 
         self.queries.set(0);
         self.misses.set(0);
     }
+
+    pub fn set_max_age(&mut self, max_age: usize) {
+        self.max_age = max_age;
+    }
+
+    /// Statistics for the most recently completed frame
+    pub fn last_frame_stats(&self) -> CacheStats {
+        self.last_stats
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unused_entries_are_evicted_after_max_age_frames() {
+        let mut cache = KeyedCache::with_max_age(2);
+        cache.get_or_insert_with(1, || "a");
+
+        cache.age();
+        assert_eq!(cache.last_frame_stats().entries, 1);
+
+        cache.age();
+        assert_eq!(cache.last_frame_stats().entries, 0);
+    }
+
+    #[test]
+    fn lookups_keep_entries_alive() {
+        let mut cache = KeyedCache::with_max_age(2);
+        cache.get_or_insert_with(1, || "a");
+        cache.age();
+
+        cache.get_or_insert_with(1, || unreachable!());
+        cache.age();
+        assert_eq!(cache.last_frame_stats().entries, 1);
+
+        cache.age();
+        assert_eq!(cache.last_frame_stats().entries, 0);
+    }
+
+    #[test]
+    fn set_max_age_applies_to_existing_entries() {
+        let mut cache = KeyedCache::new();
+        cache.get_or_insert_with(1, || "a");
+
+        cache.set_max_age(1);
+        cache.age();
+        assert_eq!(cache.last_frame_stats().entries, 0);
+    }
+
+    #[test]
+    fn last_frame_stats_counts_hits_and_misses_per_frame() {
+        let mut cache = KeyedCache::new();
+        assert_eq!(cache.last_frame_stats(), CacheStats::default());
+
+        cache.get_or_insert_with(1, || "a");
+        cache.get_or_insert_with(1, || "b");
+        cache.get_or_insert_with(2, || "c");
+        assert_eq!(*cache.get_or_insert_with(1, || "d"), "a");
+
+        cache.age();
+        assert_eq!(
+            cache.last_frame_stats(),
+            CacheStats {
+                hits: 2,
+                misses: 2,
+                entries: 2,
+            }
+        );
+
+        cache.age();
+        assert_eq!(
+            cache.last_frame_stats(),
+            CacheStats {
+                hits: 0,
+                misses: 0,
+                entries: 2,
+            }
+        );
+    }
 }
@@ -8,6 +8,8 @@ mod compute_pipeline;
 mod context;
 mod dispatch;
 mod draw_call;
+mod error;
+mod query_set;
 mod render_pass;
 mod render_pipeline;
 mod render_texture;
@@ -18,6 +20,10 @@ mod texture;
 mod keyed_cache;
 mod pipeline_layout;
 
+pub mod prelude;
+#[cfg(feature = "util")]
+pub mod util;
+
 pub use bind_group::*;
 pub use buffer::*;
 pub use command_encoder::*;
@@ -26,6 +32,9 @@ pub use compute_pipeline::*;
 pub use context::*;
 pub use dispatch::*;
 pub use draw_call::*;
+pub use error::*;
+pub use keyed_cache::CacheStats;
+pub use query_set::*;
 pub use render_pass::*;
 pub use render_pipeline::*;
 pub use render_texture::*;
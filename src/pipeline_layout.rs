@@ -5,6 +5,7 @@ use crate::{bind_group::BindGroupLayout, context::Context};
 #[derive(Clone, Hash, PartialEq, Eq)]
 pub(crate) struct PipelineLayout {
     pub(crate) bind_group_layouts: Vec<BindGroupLayout>,
+    pub(crate) push_constant_ranges: Vec<wgpu::PushConstantRange>,
 }
 
 impl PipelineLayout {
@@ -29,7 +30,7 @@ impl PipelineLayout {
                         .create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
                             label: None,
                             bind_group_layouts: &bind_group_layout_refs,
-                            push_constant_ranges: &[],
+                            push_constant_ranges: &self.push_constant_ranges,
                         }),
                 )
             })
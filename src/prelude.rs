@@ -0,0 +1,13 @@
+//! The most commonly used types, for glob importing
+//!
+//! ```
+//! use simple_wgpu::prelude::*;
+//! ```
+
+pub use crate::{
+    BindGroup, BindGroupBuilder, Buffer, ColorAttachment, ColorTargetState, CommandEncoder,
+    ComputePass, ComputePipeline, ComputePipelineBuilder, Context, DepthStencilAttachment,
+    Dispatch, DispatchBuilder, DrawCall, DrawCallBuilder, RasteriserState, RenderPass,
+    RenderPipeline, RenderPipelineBuilder, RenderTexture, Sampler, SamplerBuilder, Shader,
+    StorageBuffer, Texture, UniformBuffer, VertexBufferLayout,
+};
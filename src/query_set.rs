@@ -0,0 +1,28 @@
+use std::sync::Arc;
+
+/// A set of GPU timestamp queries
+///
+/// Create via [Context::create_query_set](crate::Context::create_query_set), record timestamps
+/// with [CommandEncoder::write_timestamp](crate::CommandEncoder::write_timestamp), and copy the
+/// results into a buffer with [CommandEncoder::resolve_query_set](crate::CommandEncoder::resolve_query_set)
+///
+/// The equivalent to [wgpu::QuerySet]
+#[derive(Clone, Debug)]
+pub struct QuerySet {
+    pub(crate) query_set: Arc<wgpu::QuerySet>,
+    count: u32,
+}
+
+impl QuerySet {
+    pub(crate) fn new(query_set: wgpu::QuerySet, count: u32) -> Self {
+        Self {
+            query_set: Arc::new(query_set),
+            count,
+        }
+    }
+
+    /// The number of queries in the set
+    pub fn count(&self) -> u32 {
+        self.count
+    }
+}
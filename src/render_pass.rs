@@ -1,6 +1,9 @@
 use crate::{
+    bind_group::BindGroup,
+    buffer::BufferSlice,
     command_encoder::{CommandEncoder, Pass},
     draw_call::DrawCall,
+    render_pipeline::RenderPipeline,
     render_texture::RenderTexture,
 };
 
@@ -24,6 +27,138 @@ pub struct DepthStencilAttachment {
     pub stencil_ops: Option<wgpu::Operations<u32>>,
 }
 
+impl DepthStencilAttachment {
+    /// Create a writable depth/stencil attachment, that loads the existing depth and stencil
+    /// values at the start of the pass and stores them at the end
+    ///
+    /// Operations are only set for the aspects the target's format actually has
+    pub fn new(target: RenderTexture) -> Self {
+        let format = target.format();
+
+        Self {
+            depth_ops: format.has_depth_aspect().then_some(wgpu::Operations {
+                load: wgpu::LoadOp::Load,
+                store: true,
+            }),
+            stencil_ops: format.has_stencil_aspect().then_some(wgpu::Operations {
+                load: wgpu::LoadOp::Load,
+                store: true,
+            }),
+            target,
+        }
+    }
+
+    /// Create a read-only depth/stencil attachment
+    ///
+    /// Leaving both operations as `None` marks the depth and stencil aspects read-only. The
+    /// depth buffer can be tested against, and simultaneously sampled in a shader if the
+    /// texture was created with [TextureUsages::TEXTURE_BINDING](wgpu::TextureUsages::TEXTURE_BINDING).
+    /// Draw calls must disable [RasteriserState::depth_write](crate::RasteriserState::depth_write)
+    pub fn read_only(target: RenderTexture) -> Self {
+        Self {
+            target,
+            depth_ops: None,
+            stencil_ops: None,
+        }
+    }
+
+    /// Clear the depth buffer to `value` at the start of the pass
+    pub fn with_depth_clear(mut self, value: f32) -> Self {
+        self.depth_ops = Some(wgpu::Operations {
+            load: wgpu::LoadOp::Clear(value),
+            store: true,
+        });
+        self
+    }
+
+    /// Preserve the existing contents of the depth buffer at the start of the pass
+    pub fn with_depth_load(mut self) -> Self {
+        self.depth_ops = Some(wgpu::Operations {
+            load: wgpu::LoadOp::Load,
+            store: true,
+        });
+        self
+    }
+
+    /// Clear the stencil buffer to `value` at the start of the pass
+    pub fn with_stencil_clear(mut self, value: u32) -> Self {
+        self.stencil_ops = Some(wgpu::Operations {
+            load: wgpu::LoadOp::Clear(value),
+            store: true,
+        });
+        self
+    }
+}
+
+/// Viewport set via [RenderPass::set_viewport]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct Viewport {
+    pub x: f32,
+    pub y: f32,
+    pub w: f32,
+    pub h: f32,
+    pub min_depth: f32,
+    pub max_depth: f32,
+}
+
+/// Render pass state that applies to every subsequent draw call, until it is changed
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub(crate) struct DynamicState {
+    pub viewport: Option<Viewport>,
+    pub scissor_rect: Option<(u32, u32, u32, u32)>,
+    pub blend_constant: Option<wgpu::Color>,
+    pub stencil_reference: Option<u32>,
+}
+
+/// CPU-side statistics about the draw calls recorded in one or more render passes
+#[derive(Clone, Copy, Default, PartialEq, Eq, Hash, Debug)]
+pub struct DrawStats {
+    pub draw_calls: usize,
+    /// The number of triangles drawn, according to each draw's primitive topology
+    ///
+    /// Line and point draws contribute no triangles. Indirect draw calls are not included,
+    /// since their arguments are only known to the GPU
+    pub triangles: usize,
+}
+
+impl DrawStats {
+    pub(crate) fn from_draw_calls(draw_calls: &[DrawCall]) -> Self {
+        Self {
+            draw_calls: draw_calls.len(),
+            triangles: draw_calls
+                .iter()
+                .filter(|d| d.indirect.is_none())
+                .map(|d| {
+                    let topology = d.pipeline.effective_topology(d.rasteriser_state.as_ref());
+                    triangle_count(topology, d.element_range.len()) * d.instance_range.len()
+                })
+                .sum(),
+        }
+    }
+}
+
+/// The number of triangles assembled from `elements` vertices (or indices)
+fn triangle_count(topology: wgpu::PrimitiveTopology, elements: usize) -> usize {
+    match topology {
+        wgpu::PrimitiveTopology::TriangleList => elements / 3,
+        wgpu::PrimitiveTopology::TriangleStrip => elements.saturating_sub(2),
+        wgpu::PrimitiveTopology::PointList
+        | wgpu::PrimitiveTopology::LineList
+        | wgpu::PrimitiveTopology::LineStrip => 0,
+    }
+}
+
+impl std::ops::Add for DrawStats {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self {
+        Self {
+            draw_calls: self.draw_calls + other.draw_calls,
+            triangles: self.triangles + other.triangles,
+        }
+    }
+}
+
 /// Record a render pass
 ///
 /// Create via [`CommandEncoder::render_pass`].
@@ -35,6 +170,8 @@ pub struct RenderPass<'a> {
     depth_stencil_attachment: Option<DepthStencilAttachment>,
     multisample: Option<wgpu::MultisampleState>,
     draw_calls: Vec<DrawCall>,
+    dynamic_states: Vec<DynamicState>,
+    current_state: DynamicState,
     frame: &'a mut CommandEncoder,
 }
 
@@ -52,13 +189,133 @@ impl<'a> RenderPass<'a> {
             depth_stencil_attachment,
             multisample,
             draw_calls: vec![],
+            dynamic_states: vec![],
+            current_state: DynamicState::default(),
             frame,
         }
     }
 
     /// Dispatch a draw call
     pub fn draw(&mut self, draw_call: DrawCall) {
+        if let Some(indirect) = &draw_call.indirect {
+            indirect.debug_assert_indirect();
+        }
         self.draw_calls.push(draw_call);
+        self.dynamic_states.push(self.current_state);
+    }
+
+    /// Dispatch a draw call, reading the draw arguments from a GPU buffer
+    ///
+    /// The arguments are laid out as [DrawIndirect](wgpu::util::DrawIndirect), starting
+    /// `indirect_offset` bytes into `indirect_buffer`. For control over the remaining
+    /// draw state, set [DrawCall::indirect] and use [RenderPass::draw] instead
+    pub fn draw_indirect(
+        &mut self,
+        pipeline: RenderPipeline,
+        bind_groups: Vec<BindGroup>,
+        vertices: Vec<BufferSlice>,
+        indirect_buffer: BufferSlice,
+        indirect_offset: u64,
+    ) {
+        self.draw(DrawCall {
+            bind_group_offsets: vec![vec![]; bind_groups.len()],
+            bind_groups,
+            pipeline,
+            vertices,
+            indices: None,
+            index_format: wgpu::IndexFormat::Uint16,
+            indirect: Some(indirect_buffer.skip(indirect_offset)),
+            element_range: 0..0,
+            instance_range: 0..0,
+            base_vertex: 0,
+            instance_base: 0,
+            push_constant_data: vec![],
+            rasteriser_state: None,
+        });
+    }
+
+    /// Dispatch an indexed draw call, reading the draw arguments from a GPU buffer
+    ///
+    /// The arguments are laid out as [DrawIndexedIndirect](wgpu::util::DrawIndexedIndirect),
+    /// starting `indirect_offset` bytes into `indirect_buffer`
+    #[allow(clippy::too_many_arguments)]
+    pub fn draw_indexed_indirect(
+        &mut self,
+        pipeline: RenderPipeline,
+        bind_groups: Vec<BindGroup>,
+        vertices: Vec<BufferSlice>,
+        indices: BufferSlice,
+        index_format: wgpu::IndexFormat,
+        indirect_buffer: BufferSlice,
+        indirect_offset: u64,
+    ) {
+        self.draw(DrawCall {
+            bind_group_offsets: vec![vec![]; bind_groups.len()],
+            bind_groups,
+            pipeline,
+            vertices,
+            indices: Some(indices),
+            index_format,
+            indirect: Some(indirect_buffer.skip(indirect_offset)),
+            element_range: 0..0,
+            instance_range: 0..0,
+            base_vertex: 0,
+            instance_base: 0,
+            push_constant_data: vec![],
+            rasteriser_state: None,
+        });
+    }
+
+    /// Set the viewport for all subsequent draw calls in this pass
+    ///
+    /// See wgpu's [RenderPass::set_viewport](wgpu::RenderPass::set_viewport)
+    pub fn set_viewport(&mut self, x: f32, y: f32, w: f32, h: f32, min_depth: f32, max_depth: f32) {
+        self.current_state.viewport = Some(Viewport {
+            x,
+            y,
+            w,
+            h,
+            min_depth,
+            max_depth,
+        });
+    }
+
+    /// Set the scissor rectangle for all subsequent draw calls in this pass
+    ///
+    /// See wgpu's [RenderPass::set_scissor_rect](wgpu::RenderPass::set_scissor_rect)
+    pub fn set_scissor_rect(&mut self, x: u32, y: u32, w: u32, h: u32) {
+        self.current_state.scissor_rect = Some((x, y, w, h));
+    }
+
+    /// Set the constant color used by [BlendFactor::Constant](wgpu::BlendFactor::Constant),
+    /// for all subsequent draw calls in this pass
+    ///
+    /// See wgpu's [RenderPass::set_blend_constant](wgpu::RenderPass::set_blend_constant)
+    pub fn set_blend_constant(&mut self, color: wgpu::Color) {
+        self.current_state.blend_constant = Some(color);
+    }
+
+    /// Set the stencil reference value for all subsequent draw calls in this pass
+    ///
+    /// Draw calls that set [RasteriserState::stencil_reference](crate::RasteriserState::stencil_reference)
+    /// use their own reference value instead
+    pub fn set_stencil_reference(&mut self, reference: u32) {
+        self.current_state.stencil_reference = Some(reference);
+    }
+
+    /// The number of draw calls recorded so far
+    pub fn draw_call_count(&self) -> usize {
+        self.draw_calls.len()
+    }
+
+    /// Statistics for the draw calls recorded so far
+    pub fn stats(&self) -> DrawStats {
+        DrawStats::from_draw_calls(&self.draw_calls)
+    }
+
+    /// Whether no draw calls have been recorded yet
+    pub fn is_empty(&self) -> bool {
+        self.draw_calls.is_empty()
     }
 }
 
@@ -70,6 +327,46 @@ impl<'a> Drop for RenderPass<'a> {
             depth_stencil_attachment: self.depth_stencil_attachment.take(),
             multisample: self.multisample,
             draw_calls: self.draw_calls.drain(..).collect(),
+            dynamic_states: self.dynamic_states.drain(..).collect(),
         });
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn draw_stats_add_sums_each_field() {
+        let a = DrawStats {
+            draw_calls: 2,
+            triangles: 10,
+        };
+        let b = DrawStats {
+            draw_calls: 3,
+            triangles: 5,
+        };
+
+        assert_eq!(
+            a + b,
+            DrawStats {
+                draw_calls: 5,
+                triangles: 15,
+            }
+        );
+        assert_eq!(a + DrawStats::default(), a);
+    }
+
+    #[test]
+    fn triangle_count_follows_topology() {
+        use wgpu::PrimitiveTopology::*;
+
+        assert_eq!(triangle_count(TriangleList, 9), 3);
+        assert_eq!(triangle_count(TriangleList, 10), 3);
+        assert_eq!(triangle_count(TriangleStrip, 6), 4);
+        assert_eq!(triangle_count(TriangleStrip, 1), 0);
+        assert_eq!(triangle_count(LineList, 6), 0);
+        assert_eq!(triangle_count(LineStrip, 6), 0);
+        assert_eq!(triangle_count(PointList, 6), 0);
+    }
+}
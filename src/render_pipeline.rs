@@ -1,4 +1,4 @@
-use std::sync::Arc;
+use std::{num::NonZeroU32, sync::Arc};
 
 use crate::{
     bind_group::BindGroup, context::Context, draw_call::RasteriserState,
@@ -24,14 +24,77 @@ pub struct ColorTargetState {
     pub write_mask: wgpu::ColorWrites,
 }
 
+impl ColorTargetState {
+    /// Blending disabled, writes to all channels
+    pub const OPAQUE: Self = Self {
+        blend: None,
+        write_mask: wgpu::ColorWrites::ALL,
+    };
+
+    /// Blending for colors with premultiplied alpha, writes to all channels
+    pub const ALPHA_PREMULTIPLIED: Self = Self {
+        blend: Some(wgpu::BlendState::PREMULTIPLIED_ALPHA_BLENDING),
+        write_mask: wgpu::ColorWrites::ALL,
+    };
+
+    /// Conventional `SrcAlpha`/`OneMinusSrcAlpha` blending, writes to all channels
+    pub const ALPHA_CONVENTIONAL: Self = Self {
+        blend: Some(wgpu::BlendState::ALPHA_BLENDING),
+        write_mask: wgpu::ColorWrites::ALL,
+    };
+}
+
+/// The color targets for the fragment stage of a [RenderPipeline]
+///
+/// You generally won't construct this directly. Anything that converts into it
+/// (a single `Option<ColorTargetState>`, an array, a slice, or a `Vec`) can be passed to
+/// [RenderPipelineBuilder::fragment]
+#[derive(Clone, Default, Hash, PartialEq, Eq, Debug)]
+pub struct ColorTargets(Vec<Option<ColorTargetState>>);
+
+impl From<Option<ColorTargetState>> for ColorTargets {
+    fn from(target: Option<ColorTargetState>) -> Self {
+        Self(vec![target])
+    }
+}
+
+impl From<Vec<Option<ColorTargetState>>> for ColorTargets {
+    fn from(targets: Vec<Option<ColorTargetState>>) -> Self {
+        Self(targets)
+    }
+}
+
+impl From<&[Option<ColorTargetState>]> for ColorTargets {
+    fn from(targets: &[Option<ColorTargetState>]) -> Self {
+        Self(targets.to_vec())
+    }
+}
+
+impl<const N: usize> From<[Option<ColorTargetState>; N]> for ColorTargets {
+    fn from(targets: [Option<ColorTargetState>; N]) -> Self {
+        Self(targets.into())
+    }
+}
+
 /// A render pipeline
 ///
 /// Loosely equivalent to [wgpu::RenderPipeline],
 /// but minus some state that is easier to handle dynamically
+///
+/// The primitive topology set on the pipeline can be overridden per-draw via
+/// [RasteriserState::topology]
 #[derive(Clone, Debug)]
 pub struct RenderPipeline {
     vertex: (EntryPoint, Vec<VertexBufferLayout>),
     fragment: Option<(EntryPoint, Vec<Option<ColorTargetState>>)>,
+    topology: wgpu::PrimitiveTopology,
+    strip_index_format: Option<wgpu::IndexFormat>,
+    conservative: bool,
+    unclipped_depth: bool,
+    alpha_to_coverage: bool,
+    multiview: Option<NonZeroU32>,
+    depth_test: Option<(wgpu::CompareFunction, bool)>,
+    push_constant_ranges: Vec<wgpu::PushConstantRange>,
     label: Option<String>,
 }
 
@@ -40,10 +103,55 @@ pub(crate) struct RenderPipelineCacheKey {
     layout: PipelineLayout,
     vertex: (EntryPoint, Vec<VertexBufferLayout>),
     fragment: Option<(EntryPoint, Vec<Option<ColorTargetState>>)>,
+    topology: wgpu::PrimitiveTopology,
+    strip_index_format: Option<wgpu::IndexFormat>,
+    conservative: bool,
+    unclipped_depth: bool,
+    alpha_to_coverage: bool,
+    multiview: Option<NonZeroU32>,
+    depth_test: Option<(wgpu::CompareFunction, bool)>,
     rasteriser_state: RasteriserState,
+    color_formats: Vec<wgpu::TextureFormat>,
+    depth_format: Option<wgpu::TextureFormat>,
+    multisample: Option<wgpu::MultisampleState>,
 }
 
 impl RenderPipeline {
+    /// The wgpu [BindGroupLayout](wgpu::BindGroupLayout) at `index`, when this pipeline is used
+    /// with `bind_groups`
+    ///
+    /// This is the same cached layout object the pipeline itself is built with, so it is
+    /// compatible with bind groups created outside of simple-wgpu. Returns `None` if `index`
+    /// is out of range
+    pub fn get_bind_group_layout(
+        &self,
+        index: u32,
+        bind_groups: &[BindGroup],
+        context: &Context,
+    ) -> Option<Arc<wgpu::BindGroupLayout>> {
+        self.layout(bind_groups)
+            .bind_group_layouts
+            .get(index as usize)
+            .map(|layout| layout.get_or_build(context))
+    }
+
+    fn layout(&self, bind_groups: &[BindGroup]) -> PipelineLayout {
+        PipelineLayout {
+            bind_group_layouts: bind_groups.iter().map(|b| b.build_layout()).collect(),
+            push_constant_ranges: self.push_constant_ranges.clone(),
+        }
+    }
+
+    /// The topology a draw with the given rasteriser state assembles its primitives with
+    pub(crate) fn effective_topology(
+        &self,
+        rasteriser_state: Option<&RasteriserState>,
+    ) -> wgpu::PrimitiveTopology {
+        rasteriser_state
+            .and_then(|r| r.topology)
+            .unwrap_or(self.topology)
+    }
+
     pub(crate) fn get_or_build(
         &self,
         color_formats: &[wgpu::TextureFormat],
@@ -53,17 +161,42 @@ impl RenderPipeline {
         bind_groups: &[BindGroup],
         context: &Context,
     ) -> Arc<wgpu::RenderPipeline> {
-        let layout = PipelineLayout {
-            bind_group_layouts: bind_groups.iter().map(|b| b.build_layout()).collect(),
+        debug_assert_eq!(
+            self.fragment.as_ref().map_or(0, |f| f.1.len()),
+            color_formats.len(),
+            "render pipeline color targets don't match the render pass color attachments"
+        );
+
+        let layout = self.layout(bind_groups);
+
+        let topology = self.effective_topology(Some(rasteriser_state));
+        let strip_index_format = if topology.is_strip() {
+            self.strip_index_format
+        } else {
+            None
         };
 
+        let (depth_compare, depth_write_enabled) = self
+            .depth_test
+            .unwrap_or((rasteriser_state.depth_compare, rasteriser_state.depth_write));
+
         let mut pipeline_cache = context.ctx.caches.render_pipeline_cache.borrow_mut();
 
         let key = RenderPipelineCacheKey {
             layout: layout.clone(),
             vertex: self.vertex.clone(),
             fragment: self.fragment.clone(),
+            topology,
+            strip_index_format,
+            conservative: self.conservative,
+            unclipped_depth: self.unclipped_depth,
+            alpha_to_coverage: self.alpha_to_coverage,
+            multiview: self.multiview,
+            depth_test: self.depth_test,
             rasteriser_state: rasteriser_state.clone(),
+            color_formats: color_formats.to_vec(),
+            depth_format,
+            multisample: *multisample,
         };
 
         pipeline_cache
@@ -97,15 +230,32 @@ impl RenderPipeline {
                     vec![]
                 };
 
+                let mut bias = wgpu::DepthBiasState {
+                    constant: rasteriser_state.depth_bias,
+                    slope_scale: rasteriser_state.depth_bias_slope_scale,
+                    clamp: rasteriser_state.depth_bias_clamp,
+                };
+                if bias.clamp != 0.0
+                    && !context
+                        .features()
+                        .contains(wgpu::Features::DEPTH_BIAS_CLAMP)
+                {
+                    log::warn!("depth bias clamp requires Features::DEPTH_BIAS_CLAMP, ignoring");
+                    bias.clamp = 0.0;
+                }
+
                 Arc::new(context.device().create_render_pipeline(
                     &wgpu::RenderPipelineDescriptor {
                         label: self.label.as_deref(),
                         layout: Some(&layout),
                         primitive: wgpu::PrimitiveState {
+                            topology,
+                            strip_index_format,
                             front_face: rasteriser_state.front_face,
                             cull_mode: rasteriser_state.cull_mode,
                             polygon_mode: rasteriser_state.polygon_mode,
-                            ..Default::default()
+                            conservative: self.conservative,
+                            unclipped_depth: self.unclipped_depth,
                         },
                         vertex: wgpu::VertexState {
                             module: &self.vertex.0.shader,
@@ -121,13 +271,16 @@ impl RenderPipeline {
                         }),
                         depth_stencil: depth_format.map(|format| wgpu::DepthStencilState {
                             format,
-                            depth_compare: rasteriser_state.depth_compare,
-                            depth_write_enabled: rasteriser_state.depth_write,
-                            stencil: Default::default(),
-                            bias: Default::default(),
+                            depth_compare,
+                            depth_write_enabled,
+                            stencil: rasteriser_state.stencil.clone(),
+                            bias,
                         }),
-                        multisample: multisample.unwrap_or_default(),
-                        multiview: None,
+                        multisample: wgpu::MultisampleState {
+                            alpha_to_coverage_enabled: self.alpha_to_coverage,
+                            ..multisample.unwrap_or_default()
+                        },
+                        multiview: self.multiview,
                     },
                 ))
             })
@@ -140,6 +293,14 @@ impl RenderPipeline {
 pub struct RenderPipelineBuilder {
     vertex: (EntryPoint, Vec<VertexBufferLayout>),
     fragment: Option<(EntryPoint, Vec<Option<ColorTargetState>>)>,
+    topology: wgpu::PrimitiveTopology,
+    strip_index_format: Option<wgpu::IndexFormat>,
+    conservative: bool,
+    unclipped_depth: bool,
+    alpha_to_coverage: bool,
+    multiview: Option<NonZeroU32>,
+    depth_test: Option<(wgpu::CompareFunction, bool)>,
+    push_constant_ranges: Vec<wgpu::PushConstantRange>,
     label: Option<String>,
 }
 
@@ -151,6 +312,14 @@ impl RenderPipelineBuilder {
         Self {
             vertex: (entry_point.clone(), vertex_buffer_layout.into()),
             fragment: None,
+            topology: wgpu::PrimitiveTopology::TriangleList,
+            strip_index_format: None,
+            conservative: false,
+            unclipped_depth: false,
+            alpha_to_coverage: false,
+            multiview: None,
+            depth_test: None,
+            push_constant_ranges: vec![],
             label: None,
         }
     }
@@ -165,17 +334,93 @@ impl RenderPipelineBuilder {
 
     pub fn fragment<I>(mut self, entry_point: &EntryPoint, targets: I) -> Self
     where
-        I: Into<Vec<Option<ColorTargetState>>>,
+        I: Into<ColorTargets>,
     {
-        self.fragment = Some((entry_point.clone(), targets.into()));
+        self.fragment = Some((entry_point.clone(), targets.into().0));
         self
     }
 
+    /// Use a fragment stage with a single color target, with blending disabled
+    pub fn fragment_with_defaults(self, entry_point: &EntryPoint) -> Self {
+        self.fragment(entry_point, Some(ColorTargetState::default()))
+    }
+
     pub fn no_fragment(mut self) -> Self {
         self.fragment = None;
         self
     }
 
+    /// Set the primitive topology used to assemble vertices. Defaults to
+    /// [PrimitiveTopology::TriangleList](wgpu::PrimitiveTopology::TriangleList)
+    pub fn topology(mut self, topology: wgpu::PrimitiveTopology) -> Self {
+        self.topology = topology;
+        self
+    }
+
+    /// Set the index format for strip topologies. Required when drawing indexed strips,
+    /// and must match the [DrawCall::index_format](crate::DrawCall::index_format)
+    ///
+    /// Must be `None` for list topologies
+    pub fn strip_index_format(mut self, format: Option<wgpu::IndexFormat>) -> Self {
+        self.strip_index_format = format;
+        self
+    }
+
+    /// Enable conservative rasterization, which rasterizes every pixel touched by a primitive
+    ///
+    /// Requires [Features::CONSERVATIVE_RASTERIZATION](wgpu::Features::CONSERVATIVE_RASTERIZATION),
+    /// and only supports [PolygonMode::Fill](wgpu::PolygonMode::Fill)
+    pub fn conservative(mut self, enabled: bool) -> Self {
+        self.conservative = enabled;
+        self
+    }
+
+    /// Disable clipping of primitives to the `0.0..=1.0` depth range. Depth values are
+    /// clamped instead
+    ///
+    /// Requires [Features::DEPTH_CLIP_CONTROL](wgpu::Features::DEPTH_CLIP_CONTROL)
+    pub fn unclipped_depth(mut self, enabled: bool) -> Self {
+        self.unclipped_depth = enabled;
+        self
+    }
+
+    /// Derive a coverage mask from the alpha channel of the first color target.
+    /// Only has an effect when rendering to multisampled targets
+    pub fn alpha_to_coverage(mut self, enabled: bool) -> Self {
+        self.alpha_to_coverage = enabled;
+        self
+    }
+
+    /// Render to the first `views` array layers of the attachments at once
+    ///
+    /// Requires [Features::MULTIVIEW](wgpu::Features::MULTIVIEW)
+    pub fn multiview(mut self, views: NonZeroU32) -> Self {
+        self.multiview = Some(views);
+        self
+    }
+
+    /// Set the depth comparison and whether depth is written, overriding the
+    /// [RasteriserState] of each draw call
+    ///
+    /// Only has an effect when the render pass has a depth attachment
+    pub fn depth_test(mut self, compare: wgpu::CompareFunction, write: bool) -> Self {
+        self.depth_test = Some((compare, write));
+        self
+    }
+
+    /// Disable depth testing and depth writes, even when the render pass has a depth attachment
+    pub fn no_depth_test(self) -> Self {
+        self.depth_test(wgpu::CompareFunction::Always, false)
+    }
+
+    /// Declare the push constant ranges used by the shaders in this pipeline
+    ///
+    /// Requires [Features::PUSH_CONSTANTS](wgpu::Features::PUSH_CONSTANTS)
+    pub fn push_constants(mut self, ranges: Vec<wgpu::PushConstantRange>) -> Self {
+        self.push_constant_ranges = ranges;
+        self
+    }
+
     /// Set the optional debug name. This may appear in error messages and GPU profiler traces
     pub fn label(mut self, label: &str) -> Self {
         self.label = Some(label.into());
@@ -186,7 +431,49 @@ impl RenderPipelineBuilder {
         RenderPipeline {
             vertex: self.vertex,
             fragment: self.fragment,
+            topology: self.topology,
+            strip_index_format: self.strip_index_format,
+            conservative: self.conservative,
+            unclipped_depth: self.unclipped_depth,
+            alpha_to_coverage: self.alpha_to_coverage,
+            multiview: self.multiview,
+            depth_test: self.depth_test,
+            push_constant_ranges: self.push_constant_ranges,
             label: self.label,
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn single_target_converts_to_one_target() {
+        let targets: ColorTargets = Some(ColorTargetState::OPAQUE).into();
+
+        assert_eq!(targets.0, vec![Some(ColorTargetState::OPAQUE)]);
+    }
+
+    #[test]
+    fn collections_convert_in_order() {
+        let expected = vec![
+            Some(ColorTargetState::OPAQUE),
+            None,
+            Some(ColorTargetState::ALPHA_PREMULTIPLIED),
+        ];
+
+        let from_array: ColorTargets = [
+            Some(ColorTargetState::OPAQUE),
+            None,
+            Some(ColorTargetState::ALPHA_PREMULTIPLIED),
+        ]
+        .into();
+        let from_slice: ColorTargets = expected.as_slice().into();
+        let from_vec: ColorTargets = expected.clone().into();
+
+        assert_eq!(from_array.0, expected);
+        assert_eq!(from_slice.0, expected);
+        assert_eq!(from_vec.0, expected);
+    }
+}
@@ -5,6 +5,7 @@ use std::sync::Arc;
 pub struct RenderTexture {
     pub(crate) view: Arc<wgpu::TextureView>,
     pub(crate) format: wgpu::TextureFormat,
+    pub(crate) size: wgpu::Extent3d,
 }
 
 impl RenderTexture {
@@ -19,6 +20,27 @@ impl RenderTexture {
                     .create_view(&wgpu::TextureViewDescriptor::default()),
             ),
             format: surface_texture.texture.format(),
+            size: surface_texture.texture.size(),
         }
     }
+
+    /// The format of the attachment
+    pub fn format(&self) -> wgpu::TextureFormat {
+        self.format
+    }
+
+    /// The width of the attachment, in pixels
+    pub fn width(&self) -> u32 {
+        self.size.width
+    }
+
+    /// The height of the attachment, in pixels
+    pub fn height(&self) -> u32 {
+        self.size.height
+    }
+
+    /// The `(width, height)` of the attachment, in pixels
+    pub fn size(&self) -> (u32, u32) {
+        (self.size.width, self.size.height)
+    }
 }
@@ -1,4 +1,4 @@
-use std::sync::Arc;
+use std::{hash::Hash, sync::Arc};
 
 use crate::context::Context;
 
@@ -7,16 +7,24 @@ use crate::context::Context;
 /// Samplers configure texture addressing and filtering modes.
 ///
 /// Equivalent to [wgpu::Sampler]
-#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+#[derive(Clone, Debug)]
 pub struct Sampler {
-    clamp: bool,
+    address_mode_u: wgpu::AddressMode,
+    address_mode_v: wgpu::AddressMode,
+    address_mode_w: wgpu::AddressMode,
     linear: bool,
     mipmap_linear: bool,
+    lod_min_clamp: f32,
+    lod_max_clamp: f32,
+    anisotropy_clamp: u16,
+    compare: Option<wgpu::CompareFunction>,
 }
 
 impl Sampler {
     pub(crate) fn sampler_type(&self) -> wgpu::SamplerBindingType {
-        if self.linear || self.mipmap_linear {
+        if self.compare.is_some() {
+            wgpu::SamplerBindingType::Comparison
+        } else if self.linear || self.mipmap_linear {
             wgpu::SamplerBindingType::Filtering
         } else {
             wgpu::SamplerBindingType::NonFiltering
@@ -26,12 +34,6 @@ impl Sampler {
     pub(crate) fn get_or_build(&self, context: &Context) -> Arc<wgpu::Sampler> {
         let mut sampler_cache = context.ctx.caches.sampler_cache.borrow_mut();
 
-        let address_mode = if self.clamp {
-            wgpu::AddressMode::ClampToEdge
-        } else {
-            wgpu::AddressMode::Repeat
-        };
-
         let filter = if self.linear {
             wgpu::FilterMode::Linear
         } else {
@@ -48,12 +50,16 @@ impl Sampler {
             .get_or_insert_with(self.clone(), || {
                 Arc::new(context.device().create_sampler(&wgpu::SamplerDescriptor {
                     label: Some("mip"),
-                    address_mode_u: address_mode,
-                    address_mode_v: address_mode,
-                    address_mode_w: address_mode,
+                    address_mode_u: self.address_mode_u,
+                    address_mode_v: self.address_mode_v,
+                    address_mode_w: self.address_mode_w,
                     mag_filter: filter,
                     min_filter: filter,
                     mipmap_filter,
+                    lod_min_clamp: self.lod_min_clamp,
+                    lod_max_clamp: self.lod_max_clamp,
+                    anisotropy_clamp: self.anisotropy_clamp,
+                    compare: self.compare,
                     ..Default::default()
                 }))
             })
@@ -61,29 +67,107 @@ impl Sampler {
     }
 }
 
+impl Hash for Sampler {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.address_mode_u.hash(state);
+        self.address_mode_v.hash(state);
+        self.address_mode_w.hash(state);
+        self.linear.hash(state);
+        self.mipmap_linear.hash(state);
+        self.lod_min_clamp.to_bits().hash(state);
+        self.lod_max_clamp.to_bits().hash(state);
+        self.anisotropy_clamp.hash(state);
+        self.compare.hash(state);
+    }
+}
+
+impl PartialEq for Sampler {
+    fn eq(&self, other: &Self) -> bool {
+        self.address_mode_u == other.address_mode_u
+            && self.address_mode_v == other.address_mode_v
+            && self.address_mode_w == other.address_mode_w
+            && self.linear == other.linear
+            && self.mipmap_linear == other.mipmap_linear
+            && self.lod_min_clamp.to_bits() == other.lod_min_clamp.to_bits()
+            && self.lod_max_clamp.to_bits() == other.lod_max_clamp.to_bits()
+            && self.anisotropy_clamp == other.anisotropy_clamp
+            && self.compare == other.compare
+    }
+}
+
+impl Eq for Sampler {}
+
 /// Builds a [Sampler]
 pub struct SamplerBuilder {
-    clamp: bool,
+    address_mode_u: wgpu::AddressMode,
+    address_mode_v: wgpu::AddressMode,
+    address_mode_w: wgpu::AddressMode,
     linear: bool,
     mipmap_linear: bool,
+    lod_min_clamp: f32,
+    lod_max_clamp: f32,
+    anisotropy_clamp: u16,
+    compare: Option<wgpu::CompareFunction>,
 }
 
 impl SamplerBuilder {
     pub fn new() -> Self {
         Self {
-            clamp: true,
+            address_mode_u: wgpu::AddressMode::ClampToEdge,
+            address_mode_v: wgpu::AddressMode::ClampToEdge,
+            address_mode_w: wgpu::AddressMode::ClampToEdge,
             linear: true,
             mipmap_linear: true,
+            lod_min_clamp: 0.0,
+            lod_max_clamp: 32.0,
+            anisotropy_clamp: 1,
+            compare: None,
         }
     }
 
-    pub fn clamp(mut self) -> Self {
-        self.clamp = true;
+    /// Clamp texture coordinates to the edge of the texture, on all axes
+    pub fn clamp(self) -> Self {
+        self.address_mode(
+            wgpu::AddressMode::ClampToEdge,
+            wgpu::AddressMode::ClampToEdge,
+            wgpu::AddressMode::ClampToEdge,
+        )
+    }
+
+    /// Repeat the texture, on all axes
+    pub fn wrap(self) -> Self {
+        self.address_mode(
+            wgpu::AddressMode::Repeat,
+            wgpu::AddressMode::Repeat,
+            wgpu::AddressMode::Repeat,
+        )
+    }
+
+    /// Set the address mode for each axis independently
+    pub fn address_mode(
+        mut self,
+        u: wgpu::AddressMode,
+        v: wgpu::AddressMode,
+        w: wgpu::AddressMode,
+    ) -> Self {
+        self.address_mode_u = u;
+        self.address_mode_v = v;
+        self.address_mode_w = w;
+        self
+    }
+
+    pub fn address_mode_u(mut self, mode: wgpu::AddressMode) -> Self {
+        self.address_mode_u = mode;
+        self
+    }
+
+    pub fn address_mode_v(mut self, mode: wgpu::AddressMode) -> Self {
+        self.address_mode_v = mode;
         self
     }
 
-    pub fn wrap(mut self) -> Self {
-        self.clamp = false;
+    pub fn address_mode_w(mut self, mode: wgpu::AddressMode) -> Self {
+        self.address_mode_w = mode;
         self
     }
 
@@ -105,11 +189,102 @@ impl SamplerBuilder {
         self
     }
 
+    /// Clamp sampling to the mip levels between `min` and `max`. Defaults to `0.0..32.0`
+    pub fn lod_range(mut self, min: f32, max: f32) -> Self {
+        self.lod_min_clamp = min;
+        self.lod_max_clamp = max;
+        self
+    }
+
+    /// Enable anisotropic filtering, sampling at most `clamp` times. A value of 1 disables it
+    ///
+    /// Anisotropic filtering requires linear filtering for both textures and mipmaps, and
+    /// support for [DownlevelFlags::ANISOTROPIC_FILTERING](wgpu::DownlevelFlags::ANISOTROPIC_FILTERING)
+    pub fn anisotropy(mut self, clamp: u16) -> Self {
+        self.anisotropy_clamp = clamp;
+        self
+    }
+
+    /// Make this a comparison sampler, which compares sampled depth values against a
+    /// reference value using `function`. Used for sampling shadow maps
+    pub fn comparison(mut self, function: wgpu::CompareFunction) -> Self {
+        self.compare = Some(function);
+        self
+    }
+
     pub fn build(self) -> Sampler {
+        assert!(
+            self.lod_min_clamp <= self.lod_max_clamp,
+            "sampler LOD range is inverted: min {} > max {}",
+            self.lod_min_clamp,
+            self.lod_max_clamp
+        );
+
         Sampler {
-            clamp: self.clamp,
+            address_mode_u: self.address_mode_u,
+            address_mode_v: self.address_mode_v,
+            address_mode_w: self.address_mode_w,
             linear: self.linear,
             mipmap_linear: self.mipmap_linear,
+            lod_min_clamp: self.lod_min_clamp,
+            lod_max_clamp: self.lod_max_clamp,
+            anisotropy_clamp: self.anisotropy_clamp,
+            compare: self.compare,
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::hash_map::DefaultHasher, hash::Hasher};
+
+    use super::*;
+
+    fn hash_of(sampler: &Sampler) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        sampler.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    #[test]
+    fn identical_samplers_are_equal() {
+        let a = SamplerBuilder::new()
+            .lod_range(0.0, 8.0)
+            .anisotropy(4)
+            .build();
+        let b = SamplerBuilder::new()
+            .lod_range(0.0, 8.0)
+            .anisotropy(4)
+            .build();
+
+        assert_eq!(a, b);
+        assert_eq!(hash_of(&a), hash_of(&b));
+    }
+
+    #[test]
+    fn samplers_with_different_lod_ranges_differ() {
+        let a = SamplerBuilder::new().lod_range(0.0, 8.0).build();
+        let b = SamplerBuilder::new().lod_range(0.0, 4.0).build();
+
+        assert_ne!(a, b);
+    }
+
+    #[test]
+    fn lod_range_is_applied() {
+        let sampler = SamplerBuilder::new().lod_range(1.0, 4.0).build();
+
+        assert_eq!(sampler.lod_min_clamp, 1.0);
+        assert_eq!(sampler.lod_max_clamp, 4.0);
+    }
+
+    #[test]
+    fn single_level_lod_range_is_accepted() {
+        SamplerBuilder::new().lod_range(2.0, 2.0).build();
+    }
+
+    #[test]
+    #[should_panic(expected = "sampler LOD range is inverted")]
+    fn inverted_lod_range_panics() {
+        SamplerBuilder::new().lod_range(4.0, 1.0).build();
+    }
+}
@@ -8,6 +8,8 @@ use crate::context::Context;
 #[derive(Clone, Debug)]
 pub struct Shader {
     shader: Arc<wgpu::ShaderModule>,
+    #[cfg(feature = "shader-source")]
+    source: Option<Arc<str>>,
 }
 
 impl Shader {
@@ -15,16 +17,52 @@ impl Shader {
     ///
     /// It is generally easiest to use [wgpu::include_wgsl] to populate the `desc` argument.
     pub fn new(desc: wgpu::ShaderModuleDescriptor, context: &Context) -> Self {
+        #[cfg(feature = "shader-source")]
+        let source = match &desc.source {
+            wgpu::ShaderSource::Wgsl(source) => Some(Arc::from(source.as_ref())),
+            _ => None,
+        };
+
         Self {
             shader: Arc::new(context.device().create_shader_module(desc)),
+            #[cfg(feature = "shader-source")]
+            source,
         }
     }
 
+    /// Create a new shader from pre-compiled SPIR-V
+    ///
+    /// The SPIR-V is translated by wgpu, like any other shader source. To set a label, pass
+    /// a descriptor built with [wgpu::util::make_spirv] to [Shader::new] instead
+    #[cfg(feature = "spirv")]
+    pub fn from_spirv(bytes: &[u8], context: &Context) -> Self {
+        Self::new(
+            wgpu::ShaderModuleDescriptor {
+                label: None,
+                source: wgpu::ShaderSource::SpirV(wgpu::util::make_spirv_raw(bytes)),
+            },
+            context,
+        )
+    }
+
+    /// The WGSL source this shader was created from
+    ///
+    /// Only retained when the `shader-source` feature is enabled. Always `None` otherwise
+    pub fn source(&self) -> Option<&str> {
+        #[cfg(feature = "shader-source")]
+        return self.source.as_deref();
+
+        #[cfg(not(feature = "shader-source"))]
+        None
+    }
+
     /// Associate the shader with a specific entry point (named main function)
     pub fn entry_point(&self, entry_point: &str) -> EntryPoint {
         EntryPoint {
             shader: self.shader.clone(),
             entry_point: entry_point.to_string(),
+            #[cfg(feature = "shader-source")]
+            source: self.source.clone(),
         }
     }
 }
@@ -34,6 +72,37 @@ impl Shader {
 pub struct EntryPoint {
     pub(crate) shader: Arc<wgpu::ShaderModule>,
     pub(crate) entry_point: String,
+    #[cfg(feature = "shader-source")]
+    pub(crate) source: Option<Arc<str>>,
+}
+
+#[cfg(all(debug_assertions, feature = "entry-point-validation"))]
+impl EntryPoint {
+    /// Panic if the shader source doesn't contain this entry point for the given stage
+    pub(crate) fn validate(&self, stage: naga::ShaderStage) {
+        let Some(source) = &self.source else {
+            return;
+        };
+        let Ok(module) = naga::front::wgsl::parse_str(source) else {
+            // wgpu will report the parse error itself
+            return;
+        };
+
+        let available = module
+            .entry_points
+            .iter()
+            .filter(|e| e.stage == stage)
+            .map(|e| e.name.as_str())
+            .collect::<Vec<_>>();
+
+        if !available.contains(&self.entry_point.as_str()) {
+            let stage = format!("{stage:?}").to_lowercase();
+            panic!(
+                "entry point '{}' not found in {stage} shader; available: {available:?}",
+                self.entry_point
+            );
+        }
+    }
 }
 
 impl Eq for EntryPoint {}
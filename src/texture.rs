@@ -1,8 +1,14 @@
-use std::{hash::Hash, sync::Arc};
+use std::{hash::Hash, ops::Range, sync::Arc};
 
 use uuid::Uuid;
 
-use crate::{context::Context, RenderTexture};
+use crate::{
+    buffer::read_staging_buffer,
+    context::Context,
+    error::SimpleWgpuError,
+    render_pass::{ColorAttachment, DepthStencilAttachment},
+    RenderTexture,
+};
 
 /// A handle to a GPU texture
 ///
@@ -14,6 +20,21 @@ pub struct Texture {
     base_mip_level: u32,
     mip_level_count: u32,
     sample_count: u32,
+    usage: wgpu::TextureUsages,
+    view_dimension: Option<wgpu::TextureViewDimension>,
+    base_array_layer: u32,
+    array_layer_count: Option<u32>,
+    aspect: wgpu::TextureAspect,
+}
+
+/// Pixel data read back from a [Texture]
+#[derive(Clone, Debug)]
+pub struct TextureData {
+    /// Tightly packed pixel data, in the texture's native format. Array layers (or 3D slices) follow one another
+    pub data: Vec<u8>,
+    pub width: u32,
+    pub height: u32,
+    pub format: wgpu::TextureFormat,
 }
 
 /// How to bind a [Texture] to a [BindGroup](crate::BindGroup)
@@ -34,10 +55,34 @@ impl Texture {
             base_mip_level: 0,
             mip_level_count: desc.mip_level_count,
             sample_count: desc.sample_count,
+            usage: desc.usage,
+            view_dimension: None,
+            base_array_layer: 0,
+            array_layer_count: None,
+            aspect: wgpu::TextureAspect::All,
+        }
+    }
+
+    /// Wrap a texture that was created directly through wgpu
+    pub fn from_wgpu(texture: wgpu::Texture) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            base_mip_level: 0,
+            mip_level_count: texture.mip_level_count(),
+            sample_count: texture.sample_count(),
+            usage: texture.usage(),
+            view_dimension: None,
+            base_array_layer: 0,
+            array_layer_count: None,
+            aspect: wgpu::TextureAspect::All,
+            texture: Arc::new(texture),
         }
     }
 
     /// Create a texture from pixel data
+    ///
+    /// For 3D textures and texture arrays, `data` must contain each slice or layer
+    /// in turn, tightly packed
     pub fn with_data(
         desc: &wgpu::TextureDescriptor,
         data: &[u8],
@@ -46,6 +91,13 @@ impl Texture {
     ) -> Self {
         let texture = context.device().create_texture(desc);
 
+        let rows_per_image = if desc.size.depth_or_array_layers > 1 {
+            let (_, block_height) = desc.format.block_dimensions();
+            Some(desc.size.height.div_ceil(block_height))
+        } else {
+            None
+        };
+
         context.queue().write_texture(
             texture.as_image_copy(),
             data,
@@ -53,7 +105,7 @@ impl Texture {
                 offset: 0,
                 // todo: derive automatically from format?
                 bytes_per_row,
-                rows_per_image: None,
+                rows_per_image,
             },
             desc.size,
         );
@@ -64,18 +116,360 @@ impl Texture {
             base_mip_level: 0,
             mip_level_count: desc.mip_level_count,
             sample_count: desc.sample_count,
+            usage: desc.usage,
+            view_dimension: None,
+            base_array_layer: 0,
+            array_layer_count: None,
+            aspect: wgpu::TextureAspect::All,
+        }
+    }
+
+    /// Create a texture from pixel data for every mip level
+    ///
+    /// `mip_data` and `bytes_per_row` hold one entry per mip level, and must both have
+    /// `desc.mip_level_count` entries
+    pub fn with_data_mipmaps(
+        desc: &wgpu::TextureDescriptor,
+        mip_data: &[&[u8]],
+        bytes_per_row: &[u32],
+        context: &Context,
+    ) -> Self {
+        assert!(
+            mip_data.len() == desc.mip_level_count as usize
+                && bytes_per_row.len() == desc.mip_level_count as usize,
+            "expected data for {} mip levels, got {} levels with {} row sizes",
+            desc.mip_level_count,
+            mip_data.len(),
+            bytes_per_row.len()
+        );
+
+        let texture = Self::new(desc, context);
+
+        let (_, block_height) = desc.format.block_dimensions();
+
+        for (mip, (data, bytes_per_row)) in mip_data.iter().zip(bytes_per_row).enumerate() {
+            let size = texture.mip_level_size(mip as u32);
+
+            context.queue().write_texture(
+                wgpu::ImageCopyTexture {
+                    texture: &texture.texture,
+                    mip_level: mip as u32,
+                    origin: wgpu::Origin3d::ZERO,
+                    aspect: wgpu::TextureAspect::All,
+                },
+                data,
+                wgpu::ImageDataLayout {
+                    offset: 0,
+                    bytes_per_row: Some(*bytes_per_row),
+                    rows_per_image: Some(size.height.div_ceil(block_height)),
+                },
+                size,
+            );
+        }
+
+        texture
+    }
+
+    /// Create a new empty cubemap, with 6 square faces of `size` pixels
+    ///
+    /// Bind it with [Texture::cube_texture_binding] to sample it as a cube
+    pub fn cubemap(
+        size: u32,
+        format: wgpu::TextureFormat,
+        usage: wgpu::TextureUsages,
+        context: &Context,
+    ) -> Self {
+        Self::new(
+            &wgpu::TextureDescriptor {
+                label: None,
+                size: wgpu::Extent3d {
+                    width: size,
+                    height: size,
+                    depth_or_array_layers: 6,
+                },
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: wgpu::TextureDimension::D2,
+                format,
+                usage,
+                view_formats: &[format],
+            },
+            context,
+        )
+    }
+
+    /// Create a cubemap from the pixel data of its 6 faces
+    ///
+    /// Faces are in wgpu's layer order: +X, -X, +Y, -Y, +Z, -Z. Each face must be tightly packed
+    pub fn cubemap_from_faces(
+        faces: [&[u8]; 6],
+        size: u32,
+        format: wgpu::TextureFormat,
+        context: &Context,
+    ) -> Self {
+        let texture = Self::cubemap(
+            size,
+            format,
+            wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+            context,
+        );
+
+        let bytes_per_row = texture.bytes_per_row(0);
+        for (layer, data) in faces.iter().enumerate() {
+            texture.write_region(
+                wgpu::Origin3d {
+                    x: 0,
+                    y: 0,
+                    z: layer as u32,
+                },
+                wgpu::Extent3d {
+                    width: size,
+                    height: size,
+                    depth_or_array_layers: 1,
+                },
+                data,
+                Some(bytes_per_row),
+                context,
+            );
+        }
+
+        texture
+    }
+
+    /// Upload pixel data to a sub-region of the texture, leaving the rest untouched
+    ///
+    /// Writes to the first mip level and array layers of this view. Panics if the region
+    /// doesn't fit within it
+    pub fn write_region(
+        &self,
+        origin: wgpu::Origin3d,
+        extent: wgpu::Extent3d,
+        data: &[u8],
+        bytes_per_row: Option<u32>,
+        context: &Context,
+    ) {
+        let size = self.view_level_size(0);
+        assert!(
+            range_fits(origin.x, extent.width, size.width)
+                && range_fits(origin.y, extent.height, size.height)
+                && range_fits(
+                    origin.z,
+                    extent.depth_or_array_layers,
+                    size.depth_or_array_layers
+                ),
+            "region {:?} + {:?} is out of bounds for texture of size {:?}",
+            origin,
+            extent,
+            size
+        );
+
+        context.queue().write_texture(
+            self.as_image_copy(origin),
+            data,
+            wgpu::ImageDataLayout {
+                offset: 0,
+                bytes_per_row,
+                rows_per_image: None,
+            },
+            extent,
+        );
+    }
+
+    /// Upload pixel data to several sub-regions of a single mip level of this view
+    ///
+    /// Like [Texture::write_region], `mip` and the z component of each origin are relative to
+    /// the first mip level and array layer of this view.
+    ///
+    /// All regions are validated against the bounds of the mip level, and each region's data
+    /// against its size, before any data is uploaded. If any region is invalid, nothing is
+    /// uploaded, and the indices of the offending regions are returned. If `mip` is out of
+    /// range, or the format can't be uploaded as a whole (such as depth/stencil formats),
+    /// every region is invalid
+    pub fn upload_regions_batched(
+        &self,
+        regions: &[(wgpu::Origin3d, wgpu::Extent3d, &[u8])],
+        mip: u32,
+        context: &Context,
+    ) -> Result<(), Vec<usize>> {
+        let format = self.texture.format();
+        let (block_width, block_height) = format.block_dimensions();
+
+        let block_size = match format.block_size(None) {
+            Some(block_size) if mip < self.mip_level_count => block_size,
+            _ => return Err((0..regions.len()).collect()),
+        };
+
+        let mip_size = self.view_level_size(mip);
+
+        let invalid = regions
+            .iter()
+            .enumerate()
+            .filter(|(_, (origin, extent, data))| {
+                let required_len = u64::from(extent.width.div_ceil(block_width))
+                    * u64::from(block_size)
+                    * u64::from(extent.height.div_ceil(block_height))
+                    * u64::from(extent.depth_or_array_layers);
+
+                !range_fits(origin.x, extent.width, mip_size.width)
+                    || !range_fits(origin.y, extent.height, mip_size.height)
+                    || !range_fits(
+                        origin.z,
+                        extent.depth_or_array_layers,
+                        mip_size.depth_or_array_layers,
+                    )
+                    || (data.len() as u64) < required_len
+            })
+            .map(|(i, _)| i)
+            .collect::<Vec<_>>();
+
+        if !invalid.is_empty() {
+            return Err(invalid);
         }
+
+        for (origin, extent, data) in regions {
+            context.queue().write_texture(
+                self.image_copy_at(mip, *origin),
+                data,
+                wgpu::ImageDataLayout {
+                    offset: 0,
+                    bytes_per_row: Some(extent.width.div_ceil(block_width) * block_size),
+                    rows_per_image: Some(extent.height.div_ceil(block_height)),
+                },
+                *extent,
+            );
+        }
+
+        Ok(())
+    }
+
+    /// Read the contents of a single mip level back to the CPU
+    ///
+    /// The texture must have been created with [TextureUsages::COPY_SRC](wgpu::TextureUsages::COPY_SRC).
+    /// The copy is submitted to the GPU immediately, independent of any [CommandEncoder](crate::CommandEncoder),
+    /// and the returned future waits for all submitted work to complete
+    pub async fn download_mip(
+        &self,
+        mip: u32,
+        context: &Context,
+    ) -> Result<TextureData, SimpleWgpuError> {
+        if !self.usage.contains(wgpu::TextureUsages::COPY_SRC) {
+            return Err(SimpleWgpuError::MissingTextureUsage(
+                wgpu::TextureUsages::COPY_SRC,
+            ));
+        }
+
+        if mip >= self.texture.mip_level_count() {
+            return Err(SimpleWgpuError::MipLevelOutOfRange(mip));
+        }
+
+        let format = self.texture.format();
+        let size = self.mip_level_size(mip);
+
+        let (_, block_height) = format.block_dimensions();
+
+        let rows = size.height.div_ceil(block_height);
+        let bytes_per_row = self.bytes_per_row(mip);
+        let padded_bytes_per_row = bytes_per_row.div_ceil(wgpu::COPY_BYTES_PER_ROW_ALIGNMENT)
+            * wgpu::COPY_BYTES_PER_ROW_ALIGNMENT;
+
+        let staging = context.device().create_buffer(&wgpu::BufferDescriptor {
+            label: Some("download"),
+            usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+            size: u64::from(padded_bytes_per_row)
+                * u64::from(rows)
+                * u64::from(size.depth_or_array_layers),
+            mapped_at_creation: false,
+        });
+
+        let mut encoder =
+            context
+                .device()
+                .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                    label: Some("download"),
+                });
+        encoder.copy_texture_to_buffer(
+            wgpu::ImageCopyTexture {
+                texture: &self.texture,
+                mip_level: mip,
+                origin: wgpu::Origin3d::ZERO,
+                aspect: wgpu::TextureAspect::All,
+            },
+            wgpu::ImageCopyBuffer {
+                buffer: &staging,
+                layout: wgpu::ImageDataLayout {
+                    offset: 0,
+                    bytes_per_row: Some(padded_bytes_per_row),
+                    rows_per_image: Some(rows),
+                },
+            },
+            size,
+        );
+        context.queue().submit(Some(encoder.finish()));
+
+        let padded = read_staging_buffer(&staging, context).await?;
+
+        let data = padded
+            .chunks(padded_bytes_per_row as usize)
+            .flat_map(|row| &row[..bytes_per_row as usize])
+            .copied()
+            .collect();
+
+        Ok(TextureData {
+            data,
+            width: size.width,
+            height: size.height,
+            format,
+        })
+    }
+
+    /// A unique identifier for the underlying texture, suitable for use as a cache key
+    ///
+    /// Views of the same texture created by [Texture::view] share an identifier
+    pub fn id(&self) -> Uuid {
+        self.id
     }
 
     pub fn size(&self) -> wgpu::Extent3d {
         self.texture.size()
     }
 
+    /// The underlying wgpu [Texture](wgpu::Texture)
+    pub fn raw(&self) -> &wgpu::Texture {
+        &self.texture
+    }
+
     pub fn dimension(&self) -> wgpu::TextureDimension {
         self.texture.dimension()
     }
 
-    fn sample_type(&self) -> wgpu::TextureSampleType {
+    pub fn format(&self) -> wgpu::TextureFormat {
+        self.texture.format()
+    }
+
+    pub fn usage(&self) -> wgpu::TextureUsages {
+        self.usage
+    }
+
+    /// The size of the given mip level of the underlying texture
+    ///
+    /// Each dimension is halved per level, down to a minimum of 1. Array layers are not affected
+    pub fn mip_level_size(&self, mip: u32) -> wgpu::Extent3d {
+        level_size(self.texture.size(), self.texture.dimension(), mip)
+    }
+
+    /// The number of bytes in one row of texel blocks of the given mip level, without padding
+    ///
+    /// Panics for depth/stencil formats that can't be copied as a whole
+    pub fn bytes_per_row(&self, mip: u32) -> u32 {
+        unpadded_bytes_per_row(self.texture.format(), self.mip_level_size(mip).width)
+    }
+
+    /// The mip levels covered by this view
+    pub(crate) fn mip_range(&self) -> Range<u32> {
+        self.base_mip_level..self.base_mip_level + self.mip_level_count
+    }
+
+    pub(crate) fn sample_type(&self) -> wgpu::TextureSampleType {
         match self.texture.format() {
             wgpu::TextureFormat::R8Unorm
             | wgpu::TextureFormat::R8Snorm
@@ -121,27 +515,172 @@ impl Texture {
             base_mip_level,
             mip_level_count,
             sample_count: self.sample_count,
+            usage: self.usage,
+            view_dimension: self.view_dimension,
+            base_array_layer: self.base_array_layer,
+            array_layer_count: self.array_layer_count,
+            aspect: self.aspect,
         }
     }
 
+    /// A view of a single layer of a 2D texture array, as a 2D texture
+    ///
+    /// Use this to render to one face of a cubemap, or one layer of a shadow map array
+    pub fn view_as_array_layer(&self, layer: u32) -> Texture {
+        let mut texture = self.clone();
+        texture.view_dimension = Some(wgpu::TextureViewDimension::D2);
+        texture.base_array_layer = layer;
+        texture.array_layer_count = Some(1);
+        texture
+    }
+
+    /// A view of `count` layers of a 2D texture array, starting at `start`
+    pub fn view_as_array_range(&self, start: u32, count: u32) -> Texture {
+        let mut texture = self.clone();
+        texture.view_dimension = Some(wgpu::TextureViewDimension::D2Array);
+        texture.base_array_layer = start;
+        texture.array_layer_count = Some(count);
+        texture
+    }
+
+    /// The wgpu [TextureView](wgpu::TextureView) for this texture, for use with raw wgpu APIs
+    ///
+    /// Views are cached, so repeated calls return the same view
+    pub fn create_view(&self, context: &Context) -> Arc<wgpu::TextureView> {
+        self.get_or_build(context)
+    }
+
     pub fn as_render_texture(&self, context: &Context) -> RenderTexture {
         RenderTexture {
             view: self.get_or_build(context),
             format: self.texture.format(),
+            size: self.mip_level_size(self.base_mip_level),
         }
     }
 
+    /// Use this texture as a color attachment, without a resolve target
+    pub fn as_color_attachment(
+        &self,
+        ops: wgpu::Operations<wgpu::Color>,
+        context: &Context,
+    ) -> ColorAttachment {
+        ColorAttachment {
+            target: self.as_render_texture(context),
+            resolve_target: None,
+            ops,
+        }
+    }
+
+    /// Use this texture as a depth/stencil attachment
+    pub fn as_depth_attachment(
+        &self,
+        depth_ops: Option<wgpu::Operations<f32>>,
+        stencil_ops: Option<wgpu::Operations<u32>>,
+        context: &Context,
+    ) -> DepthStencilAttachment {
+        DepthStencilAttachment {
+            target: self.as_render_texture(context),
+            depth_ops,
+            stencil_ops,
+        }
+    }
+
+    /// Use a single mip level of this view as a render pass attachment
+    ///
+    /// `mip` is relative to the first mip level of this view. The view's array layers and
+    /// dimension are preserved
+    pub fn as_render_texture_mip(&self, mip: u32, context: &Context) -> RenderTexture {
+        assert!(
+            mip < self.mip_level_count,
+            "mip level {} is out of range for a view of {} mip levels",
+            mip,
+            self.mip_level_count
+        );
+
+        self.view(self.base_mip_level + mip, 1)
+            .as_render_texture(context)
+    }
+
     /// Bind this texture for sampling. Must be passed to a [BindGroup](crate::BindGroup)
     #[must_use]
     pub fn texture_binding(&self) -> TextureBinding {
-        let view_dimension = match self.texture.dimension() {
-            wgpu::TextureDimension::D1 => wgpu::TextureViewDimension::D1,
-            wgpu::TextureDimension::D2 => wgpu::TextureViewDimension::D2,
-            wgpu::TextureDimension::D3 => wgpu::TextureViewDimension::D3,
-        };
+        self.aspect_texture_binding(wgpu::TextureAspect::All, self.sample_type())
+    }
+
+    /// Bind the depth aspect of a depth/stencil texture for sampling. Must be passed to a [BindGroup](crate::BindGroup)
+    #[must_use]
+    pub fn depth_aspect_binding(&self) -> TextureBinding {
+        self.aspect_texture_binding(
+            wgpu::TextureAspect::DepthOnly,
+            wgpu::TextureSampleType::Depth,
+        )
+    }
+
+    /// Bind the stencil aspect of a depth/stencil texture for sampling. Must be passed to a [BindGroup](crate::BindGroup)
+    #[must_use]
+    pub fn stencil_aspect_binding(&self) -> TextureBinding {
+        self.aspect_texture_binding(
+            wgpu::TextureAspect::StencilOnly,
+            wgpu::TextureSampleType::Uint,
+        )
+    }
+
+    fn aspect_texture_binding(
+        &self,
+        aspect: wgpu::TextureAspect,
+        sample_type: wgpu::TextureSampleType,
+    ) -> TextureBinding {
+        debug_assert!(
+            self.usage.contains(wgpu::TextureUsages::TEXTURE_BINDING),
+            "texture must be created with TextureUsages::TEXTURE_BINDING to be sampled"
+        );
+
+        let view_dimension = self.binding_view_dimension();
+
+        let mut texture = self.clone();
+        texture.aspect = aspect;
 
         TextureBinding {
-            texture: self.clone(),
+            texture,
+            binding_type: wgpu::BindingType::Texture {
+                sample_type,
+                view_dimension,
+                multisampled: self.sample_count > 1,
+            },
+        }
+    }
+
+    /// Bind this texture as a cube map for sampling. Must be passed to a [BindGroup](crate::BindGroup)
+    ///
+    /// The texture must be a 2D texture with (at least) 6 array layers
+    #[must_use]
+    pub fn cube_texture_binding(&self) -> TextureBinding {
+        self.layered_texture_binding(wgpu::TextureViewDimension::Cube, 6)
+    }
+
+    /// Bind the first `array_layers` layers of this texture as a texture array for sampling.
+    /// Must be passed to a [BindGroup](crate::BindGroup)
+    #[must_use]
+    pub fn array_texture_binding(&self, array_layers: u32) -> TextureBinding {
+        self.layered_texture_binding(wgpu::TextureViewDimension::D2Array, array_layers)
+    }
+
+    fn layered_texture_binding(
+        &self,
+        view_dimension: wgpu::TextureViewDimension,
+        array_layers: u32,
+    ) -> TextureBinding {
+        debug_assert!(
+            self.usage.contains(wgpu::TextureUsages::TEXTURE_BINDING),
+            "texture must be created with TextureUsages::TEXTURE_BINDING to be sampled"
+        );
+
+        let mut texture = self.clone();
+        texture.view_dimension = Some(view_dimension);
+        texture.array_layer_count = Some(array_layers);
+
+        TextureBinding {
+            texture,
             binding_type: wgpu::BindingType::Texture {
                 sample_type: self.sample_type(),
                 view_dimension,
@@ -150,25 +689,90 @@ impl Texture {
         }
     }
 
-    /// Bind this texture as a storage texture. Must be passed to a [BindGroup](crate::BindGroup)
+    /// Bind this texture as a write-only storage texture. Must be passed to a [BindGroup](crate::BindGroup)
     #[must_use]
     pub fn storage_binding(&self) -> TextureBinding {
-        let view_dimension = match self.texture.dimension() {
-            wgpu::TextureDimension::D1 => wgpu::TextureViewDimension::D1,
-            wgpu::TextureDimension::D2 => wgpu::TextureViewDimension::D2,
-            wgpu::TextureDimension::D3 => wgpu::TextureViewDimension::D3,
-        };
+        self.storage_binding_with_access(wgpu::StorageTextureAccess::WriteOnly)
+    }
+
+    /// Bind this texture as a read-only storage texture. Must be passed to a [BindGroup](crate::BindGroup)
+    ///
+    /// Requires [Features::TEXTURE_ADAPTER_SPECIFIC_FORMAT_FEATURES](wgpu::Features::TEXTURE_ADAPTER_SPECIFIC_FORMAT_FEATURES)
+    /// to be enabled on the device, and a format that supports it
+    #[must_use]
+    pub fn storage_binding_readonly(&self) -> TextureBinding {
+        self.storage_binding_with_access(wgpu::StorageTextureAccess::ReadOnly)
+    }
+
+    /// Bind this texture as a read-write storage texture. Must be passed to a [BindGroup](crate::BindGroup)
+    ///
+    /// Requires [Features::TEXTURE_ADAPTER_SPECIFIC_FORMAT_FEATURES](wgpu::Features::TEXTURE_ADAPTER_SPECIFIC_FORMAT_FEATURES)
+    /// to be enabled on the device, and a format that supports it
+    #[must_use]
+    pub fn storage_binding_readwrite(&self) -> TextureBinding {
+        self.storage_binding_with_access(wgpu::StorageTextureAccess::ReadWrite)
+    }
+
+    fn storage_binding_with_access(&self, access: wgpu::StorageTextureAccess) -> TextureBinding {
+        debug_assert!(
+            self.usage.contains(wgpu::TextureUsages::STORAGE_BINDING),
+            "texture must be created with TextureUsages::STORAGE_BINDING to be bound as storage"
+        );
+
+        let view_dimension = self.binding_view_dimension();
 
         TextureBinding {
             texture: self.clone(),
             binding_type: wgpu::BindingType::StorageTexture {
-                access: wgpu::StorageTextureAccess::WriteOnly,
+                access,
                 format: self.texture.format(),
                 view_dimension,
             },
         }
     }
 
+    /// The dimension this texture is viewed as, when bound to a shader
+    fn binding_view_dimension(&self) -> wgpu::TextureViewDimension {
+        self.view_dimension
+            .unwrap_or(match self.texture.dimension() {
+                wgpu::TextureDimension::D1 => wgpu::TextureViewDimension::D1,
+                wgpu::TextureDimension::D2 => wgpu::TextureViewDimension::D2,
+                wgpu::TextureDimension::D3 => wgpu::TextureViewDimension::D3,
+            })
+    }
+
+    /// The size of a mip level of this view, relative to its first mip level, counting only
+    /// the array layers it covers
+    fn view_level_size(&self, mip: u32) -> wgpu::Extent3d {
+        let mut size = self.mip_level_size(self.base_mip_level + mip);
+        if self.texture.dimension() != wgpu::TextureDimension::D3 {
+            size.depth_or_array_layers = self
+                .array_layer_count
+                .unwrap_or(size.depth_or_array_layers - self.base_array_layer);
+        }
+        size
+    }
+
+    /// Describe a copy to or from this texture, starting at the first mip level of this view
+    ///
+    /// The z component of `origin` is relative to the first array layer of this view
+    pub(crate) fn as_image_copy(&self, origin: wgpu::Origin3d) -> wgpu::ImageCopyTexture {
+        self.image_copy_at(0, origin)
+    }
+
+    /// Describe a copy to or from a mip level of this view, relative to its first mip level
+    fn image_copy_at(&self, mip: u32, origin: wgpu::Origin3d) -> wgpu::ImageCopyTexture {
+        wgpu::ImageCopyTexture {
+            texture: &self.texture,
+            mip_level: self.base_mip_level + mip,
+            origin: wgpu::Origin3d {
+                z: origin.z + self.base_array_layer,
+                ..origin
+            },
+            aspect: wgpu::TextureAspect::All,
+        }
+    }
+
     pub(crate) fn get_or_build(&self, context: &Context) -> Arc<wgpu::TextureView> {
         let mut texture_view_cache = context.ctx.caches.texture_view_cache.borrow_mut();
 
@@ -177,12 +781,12 @@ impl Texture {
                 Arc::new(self.texture.create_view(&wgpu::TextureViewDescriptor {
                     label: None,
                     format: None,
-                    dimension: None,
-                    aspect: wgpu::TextureAspect::All,
+                    dimension: self.view_dimension,
+                    aspect: self.aspect,
                     base_mip_level: self.base_mip_level,
                     mip_level_count: Some(self.mip_level_count),
-                    base_array_layer: 0,
-                    array_layer_count: None,
+                    base_array_layer: self.base_array_layer,
+                    array_layer_count: self.array_layer_count,
                 }))
             })
             .clone()
@@ -194,6 +798,10 @@ impl Hash for Texture {
         self.id.hash(state);
         self.base_mip_level.hash(state);
         self.mip_level_count.hash(state);
+        self.view_dimension.hash(state);
+        self.base_array_layer.hash(state);
+        self.array_layer_count.hash(state);
+        self.aspect.hash(state);
     }
 }
 
@@ -202,7 +810,115 @@ impl PartialEq for Texture {
         self.id == other.id
             && self.base_mip_level == other.base_mip_level
             && self.mip_level_count == other.mip_level_count
+            && self.view_dimension == other.view_dimension
+            && self.base_array_layer == other.base_array_layer
+            && self.array_layer_count == other.array_layer_count
+            && self.aspect == other.aspect
     }
 }
 
 impl Eq for Texture {}
+
+/// Whether `extent` texels starting at `origin` fit within `size`, without overflowing
+fn range_fits(origin: u32, extent: u32, size: u32) -> bool {
+    origin.checked_add(extent).is_some_and(|end| end <= size)
+}
+
+/// The size of mip level `mip` of a texture with the given base size
+fn level_size(size: wgpu::Extent3d, dimension: wgpu::TextureDimension, mip: u32) -> wgpu::Extent3d {
+    size.mip_level_size(mip, dimension)
+}
+
+/// The number of bytes in a row of `width` texels, rounded up to whole texel blocks
+fn unpadded_bytes_per_row(format: wgpu::TextureFormat, width: u32) -> u32 {
+    let (block_width, _) = format.block_dimensions();
+    let block_size = format
+        .block_size(None)
+        .expect("texture format doesn't have a fixed texel size");
+
+    width.div_ceil(block_width) * block_size
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn extent(width: u32, height: u32, depth_or_array_layers: u32) -> wgpu::Extent3d {
+        wgpu::Extent3d {
+            width,
+            height,
+            depth_or_array_layers,
+        }
+    }
+
+    #[test]
+    fn range_fits_rejects_overflow() {
+        assert!(range_fits(2, 2, 4));
+        assert!(!range_fits(3, 2, 4));
+        assert!(!range_fits(u32::MAX, 2, 4));
+    }
+
+    #[test]
+    fn level_size_halves_down_to_one() {
+        let size = extent(16, 4, 1);
+
+        assert_eq!(
+            level_size(size, wgpu::TextureDimension::D2, 1),
+            extent(8, 2, 1)
+        );
+        assert_eq!(
+            level_size(size, wgpu::TextureDimension::D2, 3),
+            extent(2, 1, 1)
+        );
+        assert_eq!(
+            level_size(size, wgpu::TextureDimension::D2, 4),
+            extent(1, 1, 1)
+        );
+    }
+
+    #[test]
+    fn level_size_keeps_array_layers_but_halves_depth() {
+        let size = extent(8, 8, 6);
+
+        assert_eq!(
+            level_size(size, wgpu::TextureDimension::D2, 1),
+            extent(4, 4, 6)
+        );
+        assert_eq!(
+            level_size(size, wgpu::TextureDimension::D3, 1),
+            extent(4, 4, 3)
+        );
+    }
+
+    #[test]
+    fn bytes_per_row_counts_texels() {
+        assert_eq!(
+            unpadded_bytes_per_row(wgpu::TextureFormat::Rgba8Unorm, 5),
+            20
+        );
+        assert_eq!(unpadded_bytes_per_row(wgpu::TextureFormat::R16Float, 3), 6);
+    }
+
+    #[test]
+    fn bytes_per_row_rounds_up_to_whole_blocks() {
+        // BC1 stores each 4x4 block in 8 bytes
+        assert_eq!(
+            unpadded_bytes_per_row(wgpu::TextureFormat::Bc1RgbaUnorm, 8),
+            16
+        );
+        assert_eq!(
+            unpadded_bytes_per_row(wgpu::TextureFormat::Bc1RgbaUnorm, 5),
+            16
+        );
+        assert_eq!(
+            unpadded_bytes_per_row(wgpu::TextureFormat::Bc1RgbaUnorm, 1),
+            8
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "texture format doesn't have a fixed texel size")]
+    fn bytes_per_row_rejects_combined_depth_stencil() {
+        unpadded_bytes_per_row(wgpu::TextureFormat::Depth24PlusStencil8, 4);
+    }
+}
@@ -0,0 +1,97 @@
+//! Common vertex types
+//!
+//! These aren't meant to cover every use case, but they are enough to get started,
+//! and show the pattern for defining your own vertex types.
+
+use std::mem;
+
+use bytemuck::{Pod, Zeroable};
+
+use crate::render_pipeline::VertexBufferLayout;
+
+/// A vertex with only a position
+#[repr(C)]
+#[derive(Clone, Copy, Default, PartialEq, Debug, Pod, Zeroable)]
+pub struct VertexP {
+    pub position: [f32; 3],
+}
+
+impl VertexP {
+    /// The layout of a vertex buffer containing this vertex type
+    pub fn layout() -> VertexBufferLayout {
+        VertexBufferLayout {
+            array_stride: mem::size_of::<Self>() as wgpu::BufferAddress,
+            step_mode: wgpu::VertexStepMode::Vertex,
+            attributes: wgpu::vertex_attr_array![0 => Float32x3].to_vec(),
+        }
+    }
+}
+
+/// A vertex with a position and texture coordinates
+#[repr(C)]
+#[derive(Clone, Copy, Default, PartialEq, Debug, Pod, Zeroable)]
+pub struct VertexPT {
+    pub position: [f32; 3],
+    pub tex_coord: [f32; 2],
+}
+
+impl VertexPT {
+    /// The layout of a vertex buffer containing this vertex type
+    pub fn layout() -> VertexBufferLayout {
+        VertexBufferLayout {
+            array_stride: mem::size_of::<Self>() as wgpu::BufferAddress,
+            step_mode: wgpu::VertexStepMode::Vertex,
+            attributes: wgpu::vertex_attr_array![0 => Float32x3, 1 => Float32x2].to_vec(),
+        }
+    }
+}
+
+/// A vertex with a position, normal, and texture coordinates
+#[repr(C)]
+#[derive(Clone, Copy, Default, PartialEq, Debug, Pod, Zeroable)]
+pub struct VertexPNT {
+    pub position: [f32; 3],
+    pub normal: [f32; 3],
+    pub tex_coord: [f32; 2],
+}
+
+impl VertexPNT {
+    /// The layout of a vertex buffer containing this vertex type
+    pub fn layout() -> VertexBufferLayout {
+        VertexBufferLayout {
+            array_stride: mem::size_of::<Self>() as wgpu::BufferAddress,
+            step_mode: wgpu::VertexStepMode::Vertex,
+            attributes: wgpu::vertex_attr_array![0 => Float32x3, 1 => Float32x3, 2 => Float32x2]
+                .to_vec(),
+        }
+    }
+}
+
+/// A vertex with a position, normal, texture coordinates, and tangent
+///
+/// The `w` component of the tangent holds the handedness of the tangent frame
+#[repr(C)]
+#[derive(Clone, Copy, Default, PartialEq, Debug, Pod, Zeroable)]
+pub struct VertexPNTT {
+    pub position: [f32; 3],
+    pub normal: [f32; 3],
+    pub tex_coord: [f32; 2],
+    pub tangent: [f32; 4],
+}
+
+impl VertexPNTT {
+    /// The layout of a vertex buffer containing this vertex type
+    pub fn layout() -> VertexBufferLayout {
+        VertexBufferLayout {
+            array_stride: mem::size_of::<Self>() as wgpu::BufferAddress,
+            step_mode: wgpu::VertexStepMode::Vertex,
+            attributes: wgpu::vertex_attr_array![
+                0 => Float32x3,
+                1 => Float32x3,
+                2 => Float32x2,
+                3 => Float32x4
+            ]
+            .to_vec(),
+        }
+    }
+}
@@ -0,0 +1,154 @@
+//! Requires a GPU adapter, so it is ignored by default. Run with `cargo test -- --ignored`
+
+use std::borrow::Cow;
+
+use simple_wgpu::{
+    BindGroupBuilder, Buffer, ColorAttachment, CommandEncoder, ComputePipelineBuilder, Context,
+    ContextOptions, DispatchBuilder, DrawCallBuilder, RenderPipelineBuilder, Shader, Texture,
+};
+
+const COMPUTE_SHADER: &str = r#"
+@group(0) @binding(0)
+var<storage, read_write> color: vec4<f32>;
+
+@compute @workgroup_size(1)
+fn cs_main() {
+    color = vec4<f32>(0.0, 1.0, 0.0, 1.0);
+}
+"#;
+
+const RENDER_SHADER: &str = r#"
+@group(0) @binding(0)
+var<storage, read> color: vec4<f32>;
+
+@vertex
+fn vs_main(@builtin(vertex_index) index: u32) -> @builtin(position) vec4<f32> {
+    let uv = vec2<f32>(f32((index << 1u) & 2u), f32(index & 2u));
+    return vec4<f32>(uv * 2.0 - 1.0, 0.0, 1.0);
+}
+
+@fragment
+fn fs_main() -> @location(0) vec4<f32> {
+    return color;
+}
+"#;
+
+#[test]
+#[ignore = "requires a GPU adapter"]
+fn render_pass_reads_output_of_preceding_compute_pass() {
+    pollster::block_on(async {
+        let instance = wgpu::Instance::default();
+        let context = Context::from_instance(&instance, ContextOptions::default())
+            .await
+            .expect("no GPU adapter available");
+
+        let compute_shader = Shader::new(
+            wgpu::ShaderModuleDescriptor {
+                label: Some("write color"),
+                source: wgpu::ShaderSource::Wgsl(Cow::Borrowed(COMPUTE_SHADER)),
+            },
+            &context,
+        );
+        let render_shader = Shader::new(
+            wgpu::ShaderModuleDescriptor {
+                label: Some("read color"),
+                source: wgpu::ShaderSource::Wgsl(Cow::Borrowed(RENDER_SHADER)),
+            },
+            &context,
+        );
+
+        let storage = Buffer::new(
+            Some("color"),
+            wgpu::BufferUsages::STORAGE,
+            std::mem::size_of::<[f32; 4]>(),
+            &context,
+        );
+
+        let target = Texture::new(
+            &wgpu::TextureDescriptor {
+                label: Some("target"),
+                size: wgpu::Extent3d {
+                    width: 4,
+                    height: 4,
+                    depth_or_array_layers: 1,
+                },
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: wgpu::TextureDimension::D2,
+                format: wgpu::TextureFormat::Rgba8Unorm,
+                usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC,
+                view_formats: &[],
+            },
+            &context,
+        );
+
+        let compute_bind_group = BindGroupBuilder::new()
+            .buffer(
+                0,
+                wgpu::ShaderStages::COMPUTE,
+                &storage.storage_binding(false),
+                None,
+            )
+            .build();
+        let render_bind_group = BindGroupBuilder::new()
+            .buffer(
+                0,
+                wgpu::ShaderStages::FRAGMENT,
+                &storage.storage_binding(true),
+                None,
+            )
+            .build();
+
+        let compute_pipeline =
+            ComputePipelineBuilder::with_entry_point(&compute_shader.entry_point("cs_main"))
+                .build();
+        let render_pipeline =
+            RenderPipelineBuilder::with_vertex(&render_shader.entry_point("vs_main"), [])
+                .fragment_with_defaults(&render_shader.entry_point("fs_main"))
+                .build();
+
+        {
+            let mut encoder = CommandEncoder::new(Some("interleaved"), &context);
+
+            {
+                let mut cpass = encoder.compute_pass(Some("write color"));
+                cpass.dispatch(
+                    DispatchBuilder::new(&compute_pipeline, (1, 1, 1))
+                        .bind_group(&compute_bind_group)
+                        .build(),
+                );
+            }
+
+            {
+                let mut rpass = encoder.render_pass(
+                    Some("read color"),
+                    vec![ColorAttachment {
+                        target: target.as_render_texture(&context),
+                        resolve_target: None,
+                        ops: wgpu::Operations {
+                            load: wgpu::LoadOp::Clear(wgpu::Color::RED),
+                            store: true,
+                        },
+                    }],
+                    None,
+                    None,
+                );
+                rpass.draw(
+                    DrawCallBuilder::new(&render_pipeline, vec![])
+                        .bind_group(&render_bind_group)
+                        .element_range(0..3)
+                        .build(),
+                );
+            }
+        }
+
+        let pixels = target
+            .download_mip(0, &context)
+            .await
+            .expect("failed to download render target");
+
+        for pixel in pixels.data.chunks_exact(4) {
+            assert_eq!(pixel, [0, 255, 0, 255]);
+        }
+    });
+}